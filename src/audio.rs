@@ -1,47 +1,252 @@
 use std::{
+    fmt,
     fs::File,
-    io::{BufReader, Cursor},
+    io::{BufReader, Cursor, Read, Seek},
+    path::{Path, PathBuf},
     time::Duration,
 };
 
-use rodio::{Decoder, OutputStream, Sink, Source};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source, StreamError};
+use serde::{Deserialize, Serialize};
 
-use crate::TimerApp;
+use crate::{settings::Settings, synth::SynthConfig};
 
-impl TimerApp {
-    pub(crate) fn play_audio(&mut self) -> eyre::Result<()> {
-        if self.alarm_stream.is_none() {
-            let (stream, handle) = OutputStream::try_default()?;
+/// How long a preview of the alarm plays for.
+const PREVIEW_DURATION: Duration = Duration::from_secs(3);
+
+/// The alarm sounds that are bundled with the app.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Tone {
+    #[default]
+    InCall,
+    Chime,
+    Beep,
+    Bell,
+    SoftAscending,
+}
+
+impl Tone {
+    pub(crate) const ALL: [Tone; 5] = [
+        Tone::InCall,
+        Tone::Chime,
+        Tone::Beep,
+        Tone::Bell,
+        Tone::SoftAscending,
+    ];
+
+    fn bytes(self) -> &'static [u8] {
+        match self {
+            Tone::InCall => include_bytes!("../assets/sound/in_call_alarm.ogg"),
+            Tone::Chime => include_bytes!("../assets/sound/chime.wav"),
+            Tone::Beep => include_bytes!("../assets/sound/beep.wav"),
+            Tone::Bell => include_bytes!("../assets/sound/bell.wav"),
+            Tone::SoftAscending => include_bytes!("../assets/sound/soft_ascending.wav"),
+        }
+    }
+}
+
+impl fmt::Display for Tone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Tone::InCall => "In-call alarm",
+            Tone::Chime => "Chime",
+            Tone::Beep => "Beep",
+            Tone::Bell => "Bell",
+            Tone::SoftAscending => "Soft ascending",
+        })
+    }
+}
+
+/// A timer's alarm sound, and the output stream it plays on.
+pub(crate) struct Alarm {
+    /// A custom alarm sound. If unset, the synthesized alarm or bundled tone is used.
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) tone: Tone,
+
+    /// If set, generate the alarm rather than using the bundled tone.
+    pub(crate) synth: Option<SynthConfig>,
+    pub(crate) volume: f32,
+
+    /// If set, ramp the alarm up from silence over this long.
+    pub(crate) fade_in: Option<Duration>,
+
+    /// Opens the output stream. This is only swapped out by tests, to act like there's no audio device.
+    pub(crate) output: fn() -> Result<(OutputStream, OutputStreamHandle), StreamError>,
+
+    stream: Option<(OutputStream, OutputStreamHandle, Sink)>,
+}
+
+impl Alarm {
+    /// Creates an alarm using the sound, volume and so on from the settings.
+    pub(crate) fn new(settings: &Settings) -> Self {
+        Alarm {
+            path: settings.alarm_path.clone(),
+            tone: settings.tone,
+            synth: settings.synth.clone(),
+            volume: settings.volume,
+            fade_in: settings.fade_in,
+            output: OutputStream::try_default,
+            stream: None,
+        }
+    }
+
+    /// Whether this plays the same sound as new timers get by default.
+    pub(crate) fn is_default_sound(&self, settings: &Settings) -> bool {
+        self.path == settings.alarm_path
+            && self.tone == settings.tone
+            && self.synth == settings.synth
+    }
+
+    /// Whether this is set up just like new timers are by default, volume and all.
+    pub(crate) fn is_default(&self, settings: &Settings) -> bool {
+        self.is_default_sound(settings)
+            && self.volume == settings.volume
+            && self.fade_in == settings.fade_in
+    }
+
+    fn sink(&mut self) -> eyre::Result<&Sink> {
+        if self.stream.is_none() {
+            let (stream, handle) = (self.output)()?;
             let sink = Sink::try_new(&handle)?;
-            self.alarm_stream = Some((stream, handle, sink));
+            self.stream = Some((stream, handle, sink));
         }
 
         // Unwrap is safe here.
-        let (_, _, sink) = self.alarm_stream.as_ref().unwrap();
+        let (_, _, sink) = self.stream.as_ref().unwrap();
+        Ok(sink)
+    }
 
-        match &self.alarm_path {
-            Some(path) => {
-                let file = BufReader::new(File::open(path)?);
-                let source = Decoder::new_looped(file)?.delay(Duration::from_millis(50));
-                sink.append(source.convert_samples::<f32>());
+    /// Decodes (or generates) the alarm sound, optionally looping it forever. If the custom alarm can't be loaded
+    /// (e.g. it was deleted since it was picked), this falls back to the other sounds rather than not ringing at all.
+    fn source(&self, looped: bool) -> eyre::Result<Box<dyn Source<Item = f32> + Send>> {
+        if let Some(path) = &self.path {
+            match open(path).and_then(|reader| decode(reader, looped)) {
+                Ok(source) => return Ok(source),
+                Err(err) => {
+                    eprintln!(
+                        "Failed to load the alarm at {}, using the default instead: {err:?}",
+                        path.display()
+                    );
+                }
             }
-            None => {
-                let default_alarm = include_bytes!("../assets/sound/in_call_alarm.ogg");
-                let cursor = Cursor::new(default_alarm);
+        }
 
-                let source = Decoder::new_looped(cursor)?.delay(Duration::from_millis(50));
-                sink.append(source.convert_samples::<f32>());
-            }
+        match &self.synth {
+            Some(synth) if looped => Ok(Box::new(synth.pattern().repeat_infinite())),
+            Some(synth) => Ok(synth.pattern()),
+            None => decode(Cursor::new(self.tone.bytes()), looped),
         }
+    }
+
+    /// Starts the alarm, which loops until stopped.
+    pub(crate) fn play(&mut self) -> eyre::Result<()> {
+        let source = self.source(true)?.delay(Duration::from_millis(50));
+        self.append(source)
+    }
+
+    /// Plays the alarm briefly, to hear what it sounds like.
+    pub(crate) fn preview(&mut self) -> eyre::Result<()> {
+        let source = self.source(false)?.take_duration(PREVIEW_DURATION);
+        self.append(source)
+    }
+
+    fn append<S: Source<Item = f32> + Send + 'static>(&mut self, source: S) -> eyre::Result<()> {
+        // Anything already playing (e.g. a preview) gets cut off.
+        self.stop();
+
+        let fade_in = self.fade_in;
+        let volume = self.volume;
+        let sink = self.sink()?;
+
+        match fade_in {
+            Some(fade_in) => sink.append(source.fade_in(fade_in)),
+            None => sink.append(source),
+        }
+        sink.set_volume(volume);
         sink.play();
 
         Ok(())
     }
 
-    pub(crate) fn stop_audio(&mut self) {
-        if let Some((_, _, sink)) = self.alarm_stream.as_ref() {
+    /// Changes the volume, including of anything that's currently playing.
+    pub(crate) fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+
+        if let Some((_, _, sink)) = self.stream.as_ref() {
+            sink.set_volume(volume);
+        }
+    }
+
+    pub(crate) fn stop(&mut self) {
+        if let Some((_, _, sink)) = self.stream.as_ref() {
             sink.stop();
             sink.sleep_until_end();
         }
     }
 }
+
+/// Checks that a file can be used as an alarm sound, i.e. that it exists and is in a format we can decode.
+pub(crate) fn validate(path: &Path) -> eyre::Result<()> {
+    decode(open(path)?, false)?;
+
+    Ok(())
+}
+
+fn open(path: &Path) -> eyre::Result<BufReader<File>> {
+    Ok(BufReader::new(File::open(path)?))
+}
+
+fn decode<R: Read + Seek + Send + Sync + 'static>(
+    reader: R,
+    looped: bool,
+) -> eyre::Result<Box<dyn Source<Item = f32> + Send>> {
+    let source: Box<dyn Source<Item = f32> + Send> = if looped {
+        Box::new(Decoder::new_looped(reader)?.convert_samples())
+    } else {
+        Box::new(Decoder::new(reader)?.convert_samples())
+    };
+
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn validate_accepts_bundled_alarm() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/sound/in_call_alarm.ogg");
+        assert!(validate(&path).is_ok());
+    }
+
+    #[test]
+    fn bundled_tones_decode() {
+        for tone in Tone::ALL {
+            assert!(decode(Cursor::new(tone.bytes()), false).is_ok(), "{tone}");
+        }
+    }
+
+    #[test]
+    fn validate_rejects_missing_file() {
+        let path = std::env::temp_dir().join("timerys-test-does-not-exist.ogg");
+        assert!(validate(&path).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_non_audio() {
+        let mut file = tempfile::Builder::new().suffix(".ogg").tempfile().unwrap();
+        file.write_all(b"definitely not audio").unwrap();
+
+        assert!(validate(file.path()).is_err());
+    }
+
+    #[test]
+    fn missing_custom_alarm_falls_back_to_default() {
+        let mut alarm = Alarm::new(&Settings::default());
+        alarm.path = Some(PathBuf::from("/definitely/not/a/real/alarm.ogg"));
+        assert!(alarm.source(false).is_ok());
+    }
+}
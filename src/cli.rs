@@ -0,0 +1,110 @@
+//! Command-line arguments.
+
+use std::{path::PathBuf, time::Duration};
+
+use clap::{Parser, Subcommand};
+
+use crate::duration_parser;
+
+/// A simple cross-platform timer app.
+#[derive(Clone, Debug, Default, Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub(crate) struct Args {
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    /// How long to set the timer for, e.g. `25m`, `1h 30m`, `1:30:00` or `PT25M`. A bare number is treated as
+    /// seconds.
+    #[arg(value_parser = duration_parser::parse)]
+    pub(crate) duration: Option<Duration>,
+
+    /// Start the timer immediately.
+    #[arg(long)]
+    pub(crate) start: bool,
+
+    /// The sound file to play when the timer rings.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) alarm: Option<PathBuf>,
+
+    /// A label for the timer, shown in the window title.
+    #[arg(long)]
+    pub(crate) label: Option<String>,
+
+    /// The alarm volume, from 0.0 to 1.0.
+    #[arg(long, value_parser = parse_volume)]
+    pub(crate) volume: Option<f32>,
+
+    /// Run the timer in the terminal instead of opening a window.
+    #[arg(long)]
+    pub(crate) headless: bool,
+
+    /// Open another window even if one is already running, instead of passing these arguments on to it.
+    #[arg(long)]
+    pub(crate) new_instance: bool,
+}
+
+impl Args {
+    /// Whether any of the arguments say how to set up a timer.
+    pub(crate) fn sets_up_timer(&self) -> bool {
+        self.duration.is_some()
+            || self.start
+            || self.alarm.is_some()
+            || self.label.is_some()
+            || self.volume.is_some()
+    }
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub(crate) enum Command {
+    /// Control the timer in an already running window.
+    Ctl {
+        #[command(subcommand)]
+        command: CtlCommand,
+
+        /// Print the raw JSON response.
+        #[arg(long, global = true)]
+        json: bool,
+    },
+}
+
+/// What to tell the running window to do. These act on whichever timer is selected.
+#[derive(Clone, Debug, Subcommand)]
+pub(crate) enum CtlCommand {
+    /// Start the timer, optionally setting a new duration first.
+    Start {
+        #[arg(value_parser = duration_parser::parse)]
+        duration: Option<Duration>,
+    },
+
+    /// Pause the timer.
+    Pause,
+
+    /// Resume a paused timer.
+    Resume,
+
+    /// Stop and reset the timer.
+    Reset,
+
+    /// Add time to a running countdown.
+    Add {
+        #[arg(value_parser = duration_parser::parse)]
+        duration: Duration,
+
+        /// Take the time off instead.
+        #[arg(long)]
+        subtract: bool,
+    },
+
+    /// Show how long the timer has left.
+    Status,
+}
+
+fn parse_volume(s: &str) -> Result<f32, String> {
+    let volume: f32 = s.parse().map_err(|_| format!("'{s}' is not a number"))?;
+
+    if (0.0..=1.0).contains(&volume) {
+        Ok(volume)
+    } else {
+        Err("the volume must be between 0.0 and 1.0".to_string())
+    }
+}
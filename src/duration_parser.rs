@@ -0,0 +1,410 @@
+//! Parsing of human-friendly duration strings, shared by the command line, settings and keyboard entry.
+//!
+//! The following formats are supported:
+//!
+//! - A bare number of seconds, e.g. `90` or `2.5`.
+//! - Numbers with units, e.g. `90s`, `2.5m`, `1h30m` or `1h 5m`. Units can also be spelled out, like
+//!   `5 minutes`.
+//! - Clock-style durations, e.g. `4:30` (minutes and seconds) or `1:30:00` (hours, minutes and seconds).
+//! - ISO-8601 durations, e.g. `PT25M` or `P1DT2H`.
+
+use std::{error::Error, fmt, time::Duration};
+
+/// Why a duration string couldn't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ParseDurationError {
+    /// The string was empty.
+    Empty,
+    /// Something that should have been a number wasn't one.
+    InvalidNumber(String),
+    /// A number was followed by something that isn't a known unit.
+    UnknownUnit(String),
+    /// A number wasn't followed by a unit, in a string where one was needed.
+    MissingUnit(String),
+    /// A clock-style duration had the wrong number of parts, or a part out of range (e.g. `1:75`).
+    InvalidClock(String),
+    /// An ISO-8601 duration was malformed or used an unsupported designator.
+    InvalidIso(String),
+    /// The duration is too long to represent.
+    Overflow,
+}
+
+impl fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseDurationError::Empty => write!(f, "the duration is empty"),
+            ParseDurationError::InvalidNumber(s) => write!(f, "'{s}' is not a valid number"),
+            ParseDurationError::UnknownUnit(s) => write!(
+                f,
+                "unknown unit '{s}', expected one of 'd', 'h', 'm' or 's'"
+            ),
+            ParseDurationError::MissingUnit(s) => write!(
+                f,
+                "missing a unit after '{s}', expected one of 'd', 'h', 'm' or 's'"
+            ),
+            ParseDurationError::InvalidClock(s) => write!(
+                f,
+                "'{s}' is not a valid clock duration, expected something like '4:30' or '1:30:00'"
+            ),
+            ParseDurationError::InvalidIso(s) => write!(
+                f,
+                "'{s}' is not a valid ISO-8601 duration, expected something like 'PT25M'"
+            ),
+            ParseDurationError::Overflow => write!(f, "the duration is too long"),
+        }
+    }
+}
+
+impl Error for ParseDurationError {}
+
+/// Parses a duration string in any of the supported formats.
+pub(crate) fn parse(s: &str) -> Result<Duration, ParseDurationError> {
+    let s = s.trim();
+
+    if s.is_empty() {
+        Err(ParseDurationError::Empty)
+    } else if s.starts_with(['P', 'p']) {
+        parse_iso(s)
+    } else if s.contains(':') {
+        parse_clock(s)
+    } else {
+        parse_units(s)
+    }
+}
+
+/// The hours, minutes and seconds typed into the timer display. A part is `None` if no digits have been typed for
+/// it yet.
+pub(crate) type DigitEntry = (Option<u32>, Option<u32>, Option<u32>);
+
+/// Parses the digits typed into the timer display, which fill in from the right in an `hh(...)mmss` layout -
+/// e.g. `130` is one minute and thirty seconds.
+pub(crate) fn parse_digit_entry(s: &str) -> Result<DigitEntry, ParseDurationError> {
+    if let Some(c) = s.chars().find(|c| !c.is_ascii_digit()) {
+        return Err(ParseDurationError::InvalidNumber(c.to_string()));
+    }
+
+    // All ASCII digits, so slicing by bytes is fine.
+    let (rest, seconds) = s.split_at(s.len().saturating_sub(2));
+    let (hours, minutes) = rest.split_at(rest.len().saturating_sub(2));
+
+    let to_num = |part: &str| -> Result<Option<u32>, ParseDurationError> {
+        if part.is_empty() {
+            Ok(None)
+        } else {
+            part.parse()
+                .map(Some)
+                .map_err(|_| ParseDurationError::Overflow)
+        }
+    };
+
+    Ok((to_num(hours)?, to_num(minutes)?, to_num(seconds)?))
+}
+
+/// Like [`parse_digit_entry`], but returns the total duration.
+pub(crate) fn parse_digit_entry_duration(s: &str) -> Result<Duration, ParseDurationError> {
+    let (hours, minutes, seconds) = parse_digit_entry(s)?;
+
+    let total_secs = u64::from(hours.unwrap_or(0)) * 60 * 60
+        + u64::from(minutes.unwrap_or(0)) * 60
+        + u64::from(seconds.unwrap_or(0));
+
+    Ok(Duration::from_secs(total_secs))
+}
+
+fn secs_to_duration(secs: f64) -> Result<Duration, ParseDurationError> {
+    Duration::try_from_secs_f64(secs).map_err(|_| ParseDurationError::Overflow)
+}
+
+fn parse_number(s: &str) -> Result<f64, ParseDurationError> {
+    // Only accept plain decimals - `f64::from_str` also accepts things like "inf" and "1e5".
+    let is_decimal = !s.is_empty()
+        && s.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && s.chars().filter(|c| *c == '.').count() <= 1
+        && s != ".";
+
+    if is_decimal {
+        s.parse()
+            .map_err(|_| ParseDurationError::InvalidNumber(s.to_string()))
+    } else {
+        Err(ParseDurationError::InvalidNumber(s.to_string()))
+    }
+}
+
+fn unit_secs(unit: &str) -> Option<f64> {
+    let secs = match unit.to_ascii_lowercase().as_str() {
+        "d" | "day" | "days" => 24.0 * 60.0 * 60.0,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 60.0 * 60.0,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+        "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+        _ => return None,
+    };
+
+    Some(secs)
+}
+
+/// Parses durations like `90`, `2.5m`, `1h30m` or `1h 5m`.
+fn parse_units(s: &str) -> Result<Duration, ParseDurationError> {
+    let mut chars = s.chars().peekable();
+    let mut total = Duration::ZERO;
+    let mut terms = 0;
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut number = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+            number.push(c);
+        }
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut unit = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_alphabetic()) {
+            unit.push(c);
+        }
+
+        if number.is_empty() {
+            // Something that isn't a number or a unit (e.g. "-") gets reported as-is.
+            let bad = if unit.is_empty() {
+                chars.next().map(String::from).unwrap_or_default()
+            } else {
+                unit
+            };
+            return Err(ParseDurationError::InvalidNumber(bad));
+        }
+
+        let amount = parse_number(&number)?;
+
+        let secs = if unit.is_empty() {
+            // A bare number is only allowed on its own, where it means seconds.
+            if terms > 0 || chars.peek().is_some() {
+                return Err(ParseDurationError::MissingUnit(number));
+            }
+            amount
+        } else {
+            let multiplier = unit_secs(&unit).ok_or(ParseDurationError::UnknownUnit(unit))?;
+            amount * multiplier
+        };
+
+        total = total
+            .checked_add(secs_to_duration(secs)?)
+            .ok_or(ParseDurationError::Overflow)?;
+        terms += 1;
+    }
+
+    Ok(total)
+}
+
+/// Parses durations like `4:30` or `1:30:00`.
+fn parse_clock(s: &str) -> Result<Duration, ParseDurationError> {
+    let invalid = || ParseDurationError::InvalidClock(s.to_string());
+
+    let parts: Vec<&str> = s.split(':').map(str::trim).collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [minutes, seconds] => ("0", *minutes, *seconds),
+        [hours, minutes, seconds] => (*hours, *minutes, *seconds),
+        _ => return Err(invalid()),
+    };
+
+    let whole = |part: &str| -> Result<u64, ParseDurationError> {
+        if !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()) {
+            part.parse().map_err(|_| ParseDurationError::Overflow)
+        } else {
+            Err(invalid())
+        }
+    };
+
+    let hours = whole(hours)?;
+    let minutes = whole(minutes)?;
+    let seconds = parse_number(seconds).map_err(|_| invalid())?;
+
+    // The leading part can be as large as it likes (e.g. "90:00"), but the rest have to fit on a clock.
+    if (parts.len() == 3 && minutes >= 60) || seconds >= 60.0 {
+        return Err(invalid());
+    }
+
+    hours
+        .checked_mul(60 * 60)
+        .and_then(|secs| secs.checked_add(minutes.checked_mul(60)?))
+        .map(Duration::from_secs)
+        .and_then(|whole| whole.checked_add(secs_to_duration(seconds).ok()?))
+        .ok_or(ParseDurationError::Overflow)
+}
+
+/// Parses ISO-8601 durations like `PT25M` or `P1DT2H30M`. Years and months aren't supported, as they don't have
+/// a fixed length.
+fn parse_iso(s: &str) -> Result<Duration, ParseDurationError> {
+    let invalid = || ParseDurationError::InvalidIso(s.to_string());
+
+    let upper = s.to_ascii_uppercase();
+    let rest = upper.strip_prefix('P').ok_or_else(invalid)?;
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let (date, time) = match rest.split_once('T') {
+        Some((_, "")) => return Err(invalid()),
+        Some((date, time)) => (date, Some(time)),
+        None => (rest, None),
+    };
+
+    let mut total = Duration::ZERO;
+
+    let mut add_designators =
+        |part: &str, allowed: &[(char, f64)]| -> Result<(), ParseDurationError> {
+            let mut number = String::new();
+            let mut next_allowed = 0;
+
+            for c in part.chars() {
+                if c.is_ascii_digit() || c == '.' || c == ',' {
+                    // ISO-8601 allows a comma as the decimal separator too.
+                    number.push(if c == ',' { '.' } else { c });
+                    continue;
+                }
+
+                // Designators have to be in order, and can only appear once each.
+                let position = allowed[next_allowed..]
+                    .iter()
+                    .position(|(designator, _)| *designator == c)
+                    .ok_or_else(invalid)?;
+                let (_, multiplier) = allowed[next_allowed + position];
+                next_allowed += position + 1;
+
+                let amount = parse_number(&number).map_err(|_| invalid())?;
+                total = total
+                    .checked_add(secs_to_duration(amount * multiplier)?)
+                    .ok_or(ParseDurationError::Overflow)?;
+                number.clear();
+            }
+
+            if number.is_empty() {
+                Ok(())
+            } else {
+                Err(invalid())
+            }
+        };
+
+    add_designators(
+        date,
+        &[('W', 7.0 * 24.0 * 60.0 * 60.0), ('D', 24.0 * 60.0 * 60.0)],
+    )?;
+    if let Some(time) = time {
+        add_designators(time, &[('H', 60.0 * 60.0), ('M', 60.0), ('S', 1.0)])?;
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(parse("90s"), Ok(secs(90)));
+        assert_eq!(parse("90"), Ok(secs(90)));
+        assert_eq!(parse("25m"), Ok(secs(25 * 60)));
+        assert_eq!(parse("2.5m"), Ok(secs(150)));
+        assert_eq!(parse("1h30m"), Ok(secs(90 * 60)));
+        assert_eq!(parse("1h 5m"), Ok(secs(65 * 60)));
+        assert_eq!(parse(" 1 hour 5 minutes "), Ok(secs(65 * 60)));
+        assert_eq!(parse("1d"), Ok(secs(24 * 60 * 60)));
+        assert_eq!(parse("0.5s"), Ok(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn parses_clocks() {
+        assert_eq!(parse("1:30:00"), Ok(secs(90 * 60)));
+        assert_eq!(parse("4:30"), Ok(secs(4 * 60 + 30)));
+        assert_eq!(parse("90:00"), Ok(secs(90 * 60)));
+        assert_eq!(parse("0:05.5"), Ok(Duration::from_millis(5500)));
+    }
+
+    #[test]
+    fn parses_iso() {
+        assert_eq!(parse("PT25M"), Ok(secs(25 * 60)));
+        assert_eq!(parse("pt1h30m"), Ok(secs(90 * 60)));
+        assert_eq!(parse("P1DT2H"), Ok(secs(26 * 60 * 60)));
+        assert_eq!(parse("P1W"), Ok(secs(7 * 24 * 60 * 60)));
+        assert_eq!(parse("PT0,5S"), Ok(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        assert_eq!(parse(""), Err(ParseDurationError::Empty));
+        assert_eq!(parse("   "), Err(ParseDurationError::Empty));
+        assert_eq!(
+            parse("5x"),
+            Err(ParseDurationError::UnknownUnit("x".to_string()))
+        );
+        assert_eq!(
+            parse("1h 30"),
+            Err(ParseDurationError::MissingUnit("30".to_string()))
+        );
+        assert_eq!(
+            parse("1..5m"),
+            Err(ParseDurationError::InvalidNumber("1..5".to_string()))
+        );
+        assert_eq!(
+            parse("-5m"),
+            Err(ParseDurationError::InvalidNumber("-".to_string()))
+        );
+        assert!(matches!(
+            parse("1:75"),
+            Err(ParseDurationError::InvalidClock(_))
+        ));
+        assert!(matches!(
+            parse("1:2:3:4"),
+            Err(ParseDurationError::InvalidClock(_))
+        ));
+        assert!(matches!(parse("P"), Err(ParseDurationError::InvalidIso(_))));
+        assert!(matches!(
+            parse("PT"),
+            Err(ParseDurationError::InvalidIso(_))
+        ));
+        assert!(matches!(
+            parse("P1M"),
+            Err(ParseDurationError::InvalidIso(_))
+        ));
+        assert!(matches!(
+            parse("PT5S5M"),
+            Err(ParseDurationError::InvalidIso(_))
+        ));
+        assert_eq!(
+            parse("99999999999999999999h"),
+            Err(ParseDurationError::Overflow)
+        );
+    }
+
+    #[test]
+    fn parses_digit_entry() {
+        assert_eq!(parse_digit_entry(""), Ok((None, None, None)));
+        assert_eq!(parse_digit_entry("5"), Ok((None, None, Some(5))));
+        assert_eq!(parse_digit_entry("130"), Ok((None, Some(1), Some(30))));
+        assert_eq!(
+            parse_digit_entry("1234567"),
+            Ok((Some(123), Some(45), Some(67)))
+        );
+        assert_eq!(parse_digit_entry_duration("130"), Ok(secs(90)));
+        assert_eq!(
+            parse_digit_entry("1a"),
+            Err(ParseDurationError::InvalidNumber("a".to_string()))
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics(s in "\\PC*") {
+            let _ = parse(&s);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Turning durations into text for the UI, the title, notifications and the headless output. The reverse of
+//! [`crate::duration_parser`].
+
+use std::time::Duration;
+
+use crate::timer_core::{human_duration, TimerCore};
+
+pub(crate) fn parse_duration(duration: Duration) -> Vec<(String, &'static str)> {
+    let (hours, minutes, seconds) = human_duration(duration);
+
+    duration_parts(hours, minutes, seconds, None)
+}
+
+/// Like [`parse_duration`], but always rounds down and also shows tenths of a second. Used for the stopwatch.
+pub(crate) fn parse_stopwatch_duration(duration: Duration) -> Vec<(String, &'static str)> {
+    let total_secs = duration.as_secs();
+    let hours = total_secs / (60 * 60);
+    let minutes = (total_secs % (60 * 60)) / 60;
+    let seconds = total_secs % 60;
+    let tenths = duration.subsec_millis() / 100;
+
+    duration_parts(hours, minutes, seconds, Some(tenths))
+}
+
+fn duration_parts(
+    hours: u64,
+    minutes: u64,
+    seconds: u64,
+    tenths: Option<u32>,
+) -> Vec<(String, &'static str)> {
+    let tenths = tenths
+        .map(|tenths| format!(".{tenths}"))
+        .unwrap_or_default();
+
+    let mut ret = vec![];
+
+    if hours > 0 {
+        ret.push((format!("{hours}"), "h"));
+        ret.push((format!("{minutes:0>2}"), "m"));
+        ret.push((format!("{seconds:0>2}{tenths}"), "s"));
+    } else if minutes > 0 {
+        ret.push((format!("{minutes}"), "m"));
+        ret.push((format!("{seconds:0>2}{tenths}"), "s"));
+    } else {
+        ret.push((format!("{seconds}{tenths}"), "s"));
+    }
+
+    ret
+}
+
+/// Formats a duration like a clock with tenths of a second, e.g. `04:59.3`. Used for laps.
+pub(crate) fn precise_clock_duration(duration: Duration) -> String {
+    let total_secs = duration.as_secs();
+    let hours = total_secs / (60 * 60);
+    let minutes = (total_secs % (60 * 60)) / 60;
+    let seconds = total_secs % 60;
+    let tenths = duration.subsec_millis() / 100;
+
+    if hours > 0 {
+        format!("{hours:0>2}:{minutes:0>2}:{seconds:0>2}.{tenths}")
+    } else {
+        format!("{minutes:0>2}:{seconds:0>2}.{tenths}")
+    }
+}
+
+/// Like [`parse_stopwatch_duration`] without the tenths, but negative, e.g. `-1m 23s`. Used for how far a timer has
+/// gone over.
+pub(crate) fn parse_overtime(overtime: Duration) -> Vec<(String, &'static str)> {
+    let total_secs = overtime.as_secs();
+    let mut parts = duration_parts(
+        total_secs / (60 * 60),
+        (total_secs % (60 * 60)) / 60,
+        total_secs % 60,
+        None,
+    );
+
+    if total_secs > 0 {
+        if let Some((amount, _)) = parts.first_mut() {
+            amount.insert(0, '-');
+        }
+    }
+
+    parts
+}
+
+/// The time to show for a timer in compact places like the title, e.g. `04:59`, or `-01:23` once it's gone over.
+pub(crate) fn timer_clock(core: &TimerCore) -> String {
+    match core.overtime() {
+        Some(overtime) if overtime.as_secs() > 0 => {
+            format!(
+                "-{}",
+                clock_duration(Duration::from_secs(overtime.as_secs()))
+            )
+        }
+        _ => clock_duration(core.displayed_duration()),
+    }
+}
+
+/// Formats a duration as briefly as possible, e.g. `25m` or `1h 30m`.
+pub(crate) fn compact_duration(duration: Duration) -> String {
+    let (hours, minutes, seconds) = human_duration(duration);
+    let parts: Vec<String> = [(hours, "h"), (minutes, "m"), (seconds, "s")]
+        .into_iter()
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| format!("{amount}{unit}"))
+        .collect();
+
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Formats a duration like a clock, e.g. `04:59` or `01:04:59`.
+pub(crate) fn clock_duration(duration: Duration) -> String {
+    let (hours, minutes, seconds) = human_duration(duration);
+    if hours > 0 {
+        format!("{hours:0>2}:{minutes:0>2}:{seconds:0>2}")
+    } else {
+        format!("{minutes:0>2}:{seconds:0>2}")
+    }
+}
+
+/// Describes how many times a timer has been snoozed, e.g. "Snoozed ×2", if it has been at all.
+pub(crate) fn describe_snoozes(snoozes: u32) -> Option<String> {
+    match snoozes {
+        0 => None,
+        1 => Some("Snoozed".to_string()),
+        _ => Some(format!("Snoozed ×{snoozes}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::duration_parser::parse;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(clock_duration(secs(299)), "04:59");
+        assert_eq!(clock_duration(secs(3899)), "01:04:59");
+        assert_eq!(compact_duration(secs(0)), "0s");
+        assert_eq!(compact_duration(secs(25 * 60)), "25m");
+        assert_eq!(compact_duration(secs(90 * 60)), "1h 30m");
+        assert_eq!(
+            precise_clock_duration(Duration::from_millis(299_350)),
+            "04:59.3"
+        );
+        assert_eq!(
+            parse_overtime(secs(83)),
+            vec![("-1".to_string(), "m"), ("23".to_string(), "s")]
+        );
+        assert_eq!(
+            parse_overtime(Duration::from_millis(500)),
+            vec![("0".to_string(), "s")]
+        );
+    }
+
+    #[test]
+    fn describes_snoozes() {
+        assert_eq!(describe_snoozes(0), None);
+        assert_eq!(describe_snoozes(1), Some("Snoozed".to_string()));
+        assert_eq!(describe_snoozes(3), Some("Snoozed ×3".to_string()));
+    }
+
+    /// What a duration should be after being displayed and parsed again, given that displaying it rounds it to
+    /// the nearest second.
+    fn displayed_secs(duration: Duration) -> Duration {
+        let (hours, minutes, seconds) = human_duration(duration);
+        secs(hours * 60 * 60 + minutes * 60 + seconds)
+    }
+
+    fn join(parts: Vec<(String, &'static str)>) -> String {
+        parts
+            .into_iter()
+            .map(|(amount, unit)| format!("{amount}{unit}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    proptest! {
+        #[test]
+        fn round_trips_parse_duration(millis in 0u64..(1000 * 60 * 60 * 1000)) {
+            let duration = Duration::from_millis(millis);
+            prop_assert_eq!(parse(&join(parse_duration(duration))), Ok(displayed_secs(duration)));
+        }
+
+        #[test]
+        fn round_trips_stopwatch_duration(millis in 0u64..(1000 * 60 * 60 * 1000)) {
+            let duration = Duration::from_millis(millis);
+            let expected = Duration::from_millis(millis / 100 * 100);
+
+            let parsed = parse(&join(parse_stopwatch_duration(duration))).unwrap();
+
+            // Tenths don't convert exactly to and from floats, so allow for a tiny bit of error.
+            prop_assert!(parsed.abs_diff(expected) < Duration::from_micros(1));
+        }
+
+        #[test]
+        fn round_trips_clock_duration(millis in 0u64..(1000 * 60 * 60 * 1000)) {
+            let duration = Duration::from_millis(millis);
+            prop_assert_eq!(parse(&clock_duration(duration)), Ok(displayed_secs(duration)));
+        }
+    }
+}
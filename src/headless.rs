@@ -0,0 +1,146 @@
+//! A terminal front-end, for when a window can't be opened (e.g. over SSH). This drives the same
+//! [`TimerCore`] as the window does, just with a much simpler interface.
+
+use std::{
+    io::{self, Write},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    audio::Alarm,
+    cli::Args,
+    format::{precise_clock_duration, timer_clock},
+    settings::Settings,
+    timer_core::{IsPaused, TimerAppState, TimerCore, TimerEvent},
+};
+
+const TICK_RATE: Duration = Duration::from_millis(100);
+
+/// How often to ring the terminal bell if we can't play the alarm sound.
+const BELL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Runs a single countdown in the terminal until it's acknowledged, silenced, or the user quits.
+pub(crate) fn run(args: Args, settings: Settings) -> eyre::Result<()> {
+    let mut alarm = Alarm::new(&settings);
+    if let Some(path) = args.alarm {
+        alarm.path = Some(path);
+    }
+    if let Some(volume) = args.volume {
+        alarm.volume = volume;
+    }
+
+    let mut core = TimerCore::new(
+        args.duration.unwrap_or(settings.default_duration),
+        settings.sequence,
+    );
+    core.ring = settings.ring;
+    let label = args
+        .label
+        .map(|label| format!("{label} - "))
+        .unwrap_or_default();
+
+    // Read lines on another thread so waiting for input doesn't block the countdown.
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    println!(
+        "Press enter to pause or resume, 'l' and enter to record a lap, or 'q' and enter to quit."
+    );
+
+    core.start();
+
+    let mut stdout = io::stdout();
+    let mut bell_fallback = false;
+    let mut last_bell = Instant::now();
+
+    loop {
+        while let Ok(line) = receiver.try_recv() {
+            match line.trim() {
+                "q" => {
+                    alarm.stop();
+                    println!();
+                    return Ok(());
+                }
+                "l" => {
+                    core.lap();
+                    if let Some(lap) = core.laps.last() {
+                        println!(
+                            "Lap {}: +{} ({})",
+                            core.laps.len(),
+                            precise_clock_duration(lap.split),
+                            precise_clock_duration(lap.total)
+                        );
+                    }
+                }
+                "s" if core.is_ringing() => {
+                    alarm.stop();
+                    bell_fallback = false;
+                    core.snooze();
+                }
+                "" if core.is_ringing() => {
+                    alarm.stop();
+                    println!();
+                    return Ok(());
+                }
+                "" => core.toggle_pause(),
+                _ => {}
+            }
+        }
+
+        match core.tick() {
+            Some(TimerEvent::StartedRinging | TimerEvent::AlarmRestarted) => {
+                if let Err(err) = alarm.play() {
+                    eprintln!(
+                        "\nFailed to play the alarm, falling back to the terminal bell: {err:?}"
+                    );
+                    bell_fallback = true;
+                }
+            }
+            Some(TimerEvent::AlarmStopped) => {
+                alarm.stop();
+                bell_fallback = false;
+
+                // If it's not going to ring again, there's nothing left to wait for.
+                if core.ring.repeat.is_none() {
+                    println!();
+                    return Ok(());
+                }
+            }
+            None => {}
+        }
+
+        let status = match &core.state {
+            TimerAppState::Started {
+                is_paused: IsPaused::Paused { .. },
+                ..
+            } => " (paused)",
+            TimerAppState::Ringing { .. } => {
+                " - time's up! Press enter to stop, or 's' and enter to snooze."
+            }
+            _ => "",
+        };
+
+        // Clear the line and redraw it in place.
+        print!("\r\x1b[2K{label}{}{status}", timer_clock(&core));
+
+        if bell_fallback && last_bell.elapsed() >= BELL_INTERVAL {
+            print!("\x07");
+            last_bell = Instant::now();
+        }
+
+        stdout.flush()?;
+        thread::sleep(TICK_RATE);
+    }
+}
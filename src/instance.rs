@@ -0,0 +1,92 @@
+//! Keeps to one window at a time. The first launch holds a lock file for as long as it runs, and later launches pass
+//! their arguments on to it over the control socket instead of opening a window of their own.
+
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use eyre::{eyre, WrapErr};
+
+use crate::{
+    cli::Args,
+    ipc::{self, Request},
+};
+
+const LOCK_FILE_NAME: &str = "instance.lock";
+
+/// How many times to try reaching the running window. It might have only just started, in which case it won't be
+/// listening yet.
+const FORWARD_ATTEMPTS: u32 = 10;
+const FORWARD_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Tries to become the running instance. This returns the lock, which is held until it's dropped, or `None` if
+/// another instance already has it.
+pub(crate) fn lock() -> eyre::Result<Option<File>> {
+    lock_at(&ipc::runtime_dir().join(LOCK_FILE_NAME))
+}
+
+fn lock_at(path: &Path) -> eyre::Result<Option<File>> {
+    ipc::create_parent_dir(path)?;
+
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .wrap_err_with(|| format!("failed to open lock file {}", path.display()))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(err)) => {
+            Err(err).wrap_err_with(|| format!("failed to lock {}", path.display()))
+        }
+    }
+}
+
+/// Passes the arguments on to the running instance.
+pub(crate) fn forward(args: &Args) -> eyre::Result<()> {
+    let request = Request::try_from(args)?;
+
+    let mut attempt = 1;
+    let response = loop {
+        match ipc::send(&request) {
+            Ok(response) => break response,
+            Err(_) if attempt < FORWARD_ATTEMPTS => {
+                attempt += 1;
+                thread::sleep(FORWARD_RETRY_DELAY);
+            }
+            Err(err) => {
+                return Err(err.wrap_err(
+                    "Timerys is already running, but couldn't be reached. Use --new-instance to open another window anyway.",
+                ));
+            }
+        }
+    };
+
+    match response.error {
+        Some(error) if !response.ok => Err(eyre!(error)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_one_lock_at_a_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("timerys").join(LOCK_FILE_NAME);
+
+        let lock = lock_at(&path).unwrap();
+        assert!(lock.is_some());
+        assert!(lock_at(&path).unwrap().is_none());
+
+        drop(lock);
+        assert!(lock_at(&path).unwrap().is_some());
+    }
+}
@@ -0,0 +1,685 @@
+//! A Unix socket that scripts can use to control a running window, plus the `timerys ctl` client for it.
+//!
+//! Each request is a line of JSON, like `{"command": "start", "duration_secs": "25m"}`, and each gets a line of JSON
+//! back with the selected timer's status (or an error).
+
+use std::{
+    any::TypeId,
+    fmt, fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{MetadataExt, PermissionsExt},
+        net::UnixStream as StdUnixStream,
+    },
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use eyre::{bail, eyre, WrapErr};
+use iced::{
+    futures::{
+        channel::{mpsc, oneshot},
+        future, SinkExt,
+    },
+    Subscription,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader as AsyncBufReader},
+    net::{UnixListener, UnixStream},
+};
+
+use crate::{
+    cli::{Args, CtlCommand},
+    settings::optional_duration_secs,
+    timer::TimerMessage,
+    timer_core::{IsPaused, TimerAppState, TimerCore, TimerMode},
+};
+
+const SOCKET_DIR_NAME: &str = "timerys";
+const SOCKET_FILE_NAME: &str = "control.sock";
+
+/// How long to wait before accepting connections again after it fails.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Where the socket (and anything else that's only needed while the app is running) lives. This is under the runtime
+/// directory where there is one, and the temp directory otherwise.
+pub(crate) fn runtime_dir() -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join(SOCKET_DIR_NAME),
+        // The temp directory is shared between users, so each needs a directory of their own.
+        None => std::env::temp_dir().join(format!("{SOCKET_DIR_NAME}-{}", current_uid())),
+    }
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: geteuid has no preconditions and can't fail.
+    unsafe { libc::geteuid() }
+}
+
+pub(crate) fn socket_path() -> PathBuf {
+    runtime_dir().join(SOCKET_FILE_NAME)
+}
+
+/// Creates the directory for a file under [`runtime_dir`], if it's not there already.
+pub(crate) fn create_parent_dir(path: &Path) -> eyre::Result<()> {
+    let Some(parent) = path.parent() else {
+        return Ok(());
+    };
+
+    fs::create_dir_all(parent)
+        .wrap_err_with(|| format!("failed to create directory {}", parent.display()))?;
+
+    // Someone else could have made it first, e.g. in the shared temp directory.
+    let metadata = fs::symlink_metadata(parent)
+        .wrap_err_with(|| format!("failed to check {}", parent.display()))?;
+    if !metadata.is_dir() || metadata.uid() != current_uid() {
+        bail!(
+            "{} isn't a directory owned by the current user",
+            parent.display()
+        );
+    }
+
+    // Anyone who can reach the socket can control the timer, so keep it to ourselves.
+    fs::set_permissions(parent, fs::Permissions::from_mode(0o700))
+        .wrap_err_with(|| format!("failed to set the permissions of {}", parent.display()))
+}
+
+/// Something to do to the selected timer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub(crate) enum Request {
+    /// Starts the timer, after setting a new duration if there is one.
+    Start {
+        #[serde(
+            default,
+            rename = "duration_secs",
+            with = "optional_duration_secs",
+            skip_serializing_if = "Option::is_none"
+        )]
+        duration: Option<Duration>,
+    },
+    Pause,
+    Resume,
+    Reset,
+    /// Adds this many seconds to a running countdown, or takes them off if negative.
+    AddTime {
+        secs: i64,
+    },
+    Status,
+    /// Sets up a timer from another launch's command-line arguments, as if this window had been launched with them.
+    Open {
+        #[serde(
+            default,
+            rename = "duration_secs",
+            with = "optional_duration_secs",
+            skip_serializing_if = "Option::is_none"
+        )]
+        duration: Option<Duration>,
+        #[serde(default)]
+        start: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        alarm: Option<PathBuf>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        volume: Option<f32>,
+    },
+}
+
+impl TryFrom<&Args> for Request {
+    type Error = eyre::Report;
+
+    fn try_from(args: &Args) -> eyre::Result<Self> {
+        // The running window has its own working directory, so a relative path would point somewhere else there.
+        let alarm = args
+            .alarm
+            .as_deref()
+            .map(|path| {
+                std::path::absolute(path)
+                    .wrap_err_with(|| format!("failed to resolve {}", path.display()))
+            })
+            .transpose()?;
+
+        Ok(Request::Open {
+            duration: args.duration,
+            start: args.start,
+            alarm,
+            label: args.label.clone(),
+            volume: args.volume,
+        })
+    }
+}
+
+impl From<CtlCommand> for Request {
+    fn from(command: CtlCommand) -> Self {
+        match command {
+            CtlCommand::Start { duration } => Request::Start { duration },
+            CtlCommand::Pause => Request::Pause,
+            CtlCommand::Resume => Request::Resume,
+            CtlCommand::Reset => Request::Reset,
+            CtlCommand::Add { duration, subtract } => {
+                let secs = i64::try_from(duration.as_secs()).unwrap_or(i64::MAX);
+                Request::AddTime {
+                    secs: if subtract { -secs } else { secs },
+                }
+            }
+            CtlCommand::Status => Request::Status,
+        }
+    }
+}
+
+impl Request {
+    /// The command-line arguments this was forwarded from, if it was.
+    pub(crate) fn launch_args(&self) -> Option<Args> {
+        let Request::Open {
+            duration,
+            start,
+            alarm,
+            label,
+            volume,
+        } = self
+        else {
+            return None;
+        };
+
+        Some(Args {
+            duration: *duration,
+            start: *start,
+            alarm: alarm.clone(),
+            label: label.clone(),
+            volume: *volume,
+            ..Args::default()
+        })
+    }
+
+    /// What to send the timer to carry out this request, if anything. Requests that don't make sense for the
+    /// timer's current state are errors, so scripts find out about them.
+    pub(crate) fn timer_message(&self, core: &TimerCore) -> Result<Option<TimerMessage>, String> {
+        let is_paused = matches!(
+            core.state,
+            TimerAppState::Started {
+                is_paused: IsPaused::Paused { .. },
+                ..
+            }
+        );
+
+        match self {
+            Request::Start { .. } if !core.is_stopped() => {
+                Err("the timer has already been started; reset it first".to_string())
+            }
+            Request::Start {
+                duration: Some(duration),
+            } => {
+                if core.mode != TimerMode::Countdown {
+                    return Err("only a countdown's duration can be set".to_string());
+                }
+
+                Ok(Some(TimerMessage::UsePreset {
+                    duration: *duration,
+                    start: true,
+                }))
+            }
+            Request::Start { duration: None } => Ok(Some(TimerMessage::EnableTimer)),
+            Request::Pause if core.is_running() => Ok(Some(TimerMessage::TogglePause)),
+            Request::Pause if is_paused => Ok(None),
+            Request::Pause => Err("the timer isn't running".to_string()),
+            Request::Resume if is_paused => Ok(Some(TimerMessage::TogglePause)),
+            Request::Resume if core.is_running() => Ok(None),
+            Request::Resume => Err("the timer isn't paused".to_string()),
+            Request::Reset => Ok(Some(TimerMessage::ResetTimer)),
+            Request::AddTime { .. } if core.mode == TimerMode::Stopwatch => {
+                Err("time can't be added to a stopwatch".to_string())
+            }
+            Request::AddTime { secs } if core.is_started() => {
+                Ok(Some(TimerMessage::AdjustTime(*secs)))
+            }
+            Request::AddTime { .. } => Err("the timer hasn't been started".to_string()),
+            Request::Status | Request::Open { .. } => Ok(None),
+        }
+    }
+}
+
+/// What a timer is doing, as far as a script cares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TimerState {
+    Stopped,
+    Running,
+    Paused,
+    Ringing,
+}
+
+impl TimerState {
+    pub(crate) fn of(core: &TimerCore) -> Self {
+        match &core.state {
+            TimerAppState::Stopped => TimerState::Stopped,
+            TimerAppState::Started {
+                is_paused: IsPaused::Paused { .. },
+                ..
+            } => TimerState::Paused,
+            TimerAppState::Started { .. } => TimerState::Running,
+            TimerAppState::Ringing { .. } => TimerState::Ringing,
+        }
+    }
+}
+
+impl fmt::Display for TimerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TimerState::Stopped => "stopped",
+            TimerState::Running => "running",
+            TimerState::Paused => "paused",
+            TimerState::Ringing => "ringing",
+        })
+    }
+}
+
+/// The selected timer, as reported back after every request.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Status {
+    pub(crate) name: String,
+    pub(crate) mode: TimerMode,
+    pub(crate) state: TimerState,
+
+    /// What the window shows, e.g. `04:59` (or `-00:12` when overtime).
+    pub(crate) clock: String,
+
+    /// How long is left for a countdown, or how long has passed for a stopwatch.
+    pub(crate) remaining_secs: u64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) overtime_secs: Option<u64>,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.name, self.clock, self.state)
+    }
+}
+
+/// The reply to a request.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Response {
+    pub(crate) ok: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) status: Option<Status>,
+}
+
+impl Response {
+    pub(crate) fn ok(status: Status) -> Self {
+        Self {
+            ok: true,
+            error: None,
+            status: Some(status),
+        }
+    }
+
+    pub(crate) fn error(error: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: Some(error.into()),
+            status: None,
+        }
+    }
+}
+
+/// Sends the app's reply back to whoever made a request. This is shared so it can be part of a (cloneable) message,
+/// but only the first response is sent.
+#[derive(Clone)]
+pub(crate) struct Responder(Arc<Mutex<Option<oneshot::Sender<Response>>>>);
+
+impl Responder {
+    fn new() -> (Self, oneshot::Receiver<Response>) {
+        let (sender, receiver) = oneshot::channel();
+        (Self(Arc::new(Mutex::new(Some(sender)))), receiver)
+    }
+
+    pub(crate) fn respond(&self, response: Response) {
+        let sender = self.0.lock().ok().and_then(|mut sender| sender.take());
+        if let Some(sender) = sender {
+            // The client may have hung up already, which is fine.
+            let _ = sender.send(response);
+        }
+    }
+}
+
+impl fmt::Debug for Responder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Responder")
+    }
+}
+
+/// Listens on the control socket, producing a request (and a way to reply to it) for each line that's sent.
+pub(crate) fn listen() -> Subscription<(Request, Responder)> {
+    struct Listener;
+
+    iced::subscription::channel(TypeId::of::<Listener>(), 16, |output| async move {
+        let path = socket_path();
+        match bind(&path) {
+            Ok(listener) => serve(listener, output).await,
+            Err(err) => eprintln!("Failed to start the control socket: {err:?}"),
+        }
+
+        future::pending().await
+    })
+}
+
+/// The listening control socket. The socket file is removed when this is dropped, which happens as the app shuts
+/// down.
+struct ControlSocket {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn bind(path: &Path) -> eyre::Result<ControlSocket> {
+    create_parent_dir(path)?;
+
+    if path.exists() {
+        if StdUnixStream::connect(path).is_ok() {
+            bail!("something is already listening on {}", path.display());
+        }
+
+        // Nothing's there, so it was left behind by an instance that didn't get to clean up.
+        fs::remove_file(path)
+            .wrap_err_with(|| format!("failed to remove stale socket {}", path.display()))?;
+    }
+
+    let listener = UnixListener::bind(path)
+        .wrap_err_with(|| format!("failed to listen on {}", path.display()))?;
+
+    Ok(ControlSocket {
+        listener,
+        path: path.to_path_buf(),
+    })
+}
+
+async fn serve(socket: ControlSocket, output: mpsc::Sender<(Request, Responder)>) {
+    loop {
+        match socket.listener.accept().await {
+            Ok((stream, _)) => {
+                let output = output.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = handle_connection(stream, output).await {
+                        eprintln!("Failed to handle a control connection: {err:?}");
+                    }
+                });
+            }
+            Err(err) => {
+                eprintln!("Failed to accept a control connection: {err:?}");
+
+                // Errors like running out of file descriptors won't clear up straight away, so don't spin on them.
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+async fn handle_connection(
+    stream: UnixStream,
+    mut output: mpsc::Sender<(Request, Responder)>,
+) -> eyre::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = AsyncBufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let (responder, reply) = Responder::new();
+                output
+                    .send((request, responder))
+                    .await
+                    .wrap_err("the app has stopped listening")?;
+
+                reply
+                    .await
+                    .unwrap_or_else(|_| Response::error("the request was dropped"))
+            }
+            Err(err) => Response::error(format!("invalid request: {err}")),
+        };
+
+        let mut json = serde_json::to_string(&response)?;
+        json.push('\n');
+        writer.write_all(json.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+/// Sends a single request to the running window and waits for the reply.
+pub(crate) fn send(request: &Request) -> eyre::Result<Response> {
+    send_to(&socket_path(), request)
+}
+
+fn send_to(path: &Path, request: &Request) -> eyre::Result<Response> {
+    let mut stream = StdUnixStream::connect(path).wrap_err_with(|| {
+        format!(
+            "couldn't connect to {}; is Timerys running?",
+            path.display()
+        )
+    })?;
+
+    let mut json = serde_json::to_string(request)?;
+    json.push('\n');
+    stream
+        .write_all(json.as_bytes())
+        .wrap_err("failed to send the request")?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .wrap_err("failed to read the response")?;
+
+    serde_json::from_str(&line).wrap_err_with(|| format!("malformed response {line:?}"))
+}
+
+/// Runs `timerys ctl`.
+pub(crate) fn run_ctl(command: CtlCommand, json: bool) -> eyre::Result<()> {
+    let response = send(&command.into())?;
+
+    if json {
+        println!("{}", serde_json::to_string(&response)?);
+    } else if let Some(status) = &response.status {
+        println!("{status}");
+    }
+
+    match response.error {
+        Some(error) if !response.ok => Err(eyre!(error)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::futures::StreamExt;
+
+    use super::*;
+
+    fn status() -> Status {
+        Status {
+            name: "Tea".to_string(),
+            mode: TimerMode::Countdown,
+            state: TimerState::Running,
+            clock: "04:59".to_string(),
+            remaining_secs: 299,
+            overtime_secs: None,
+        }
+    }
+
+    #[test]
+    fn requests_parse() {
+        let parse = |json: &str| serde_json::from_str::<Request>(json).unwrap();
+
+        assert_eq!(
+            parse(r#"{"command": "start", "duration_secs": "25m"}"#),
+            Request::Start {
+                duration: Some(Duration::from_secs(25 * 60))
+            }
+        );
+        assert_eq!(
+            parse(r#"{"command": "start"}"#),
+            Request::Start { duration: None }
+        );
+        assert_eq!(
+            parse(r#"{"command": "add_time", "secs": -60}"#),
+            Request::AddTime { secs: -60 }
+        );
+        assert_eq!(parse(r#"{"command": "status"}"#), Request::Status);
+        assert!(serde_json::from_str::<Request>(r#"{"command": "explode"}"#).is_err());
+    }
+
+    #[test]
+    fn launch_args_round_trip() {
+        let args = Args {
+            duration: Some(Duration::from_secs(90)),
+            start: true,
+            label: Some("Tea".to_string()),
+            ..Args::default()
+        };
+
+        let json = serde_json::to_string(&Request::try_from(&args).unwrap()).unwrap();
+        let request: Request = serde_json::from_str(&json).unwrap();
+        let forwarded = request.launch_args().unwrap();
+
+        assert_eq!(forwarded.duration, args.duration);
+        assert!(forwarded.start);
+        assert_eq!(forwarded.label, args.label);
+        assert_eq!(forwarded.alarm, None);
+        assert!(Request::Status.launch_args().is_none());
+    }
+
+    #[test]
+    fn forwarded_alarm_paths_are_absolute() {
+        let args = Args {
+            alarm: Some(PathBuf::from("sounds/alarm.ogg")),
+            ..Args::default()
+        };
+
+        let alarm = Request::try_from(&args)
+            .unwrap()
+            .launch_args()
+            .unwrap()
+            .alarm
+            .unwrap();
+
+        assert!(alarm.is_absolute());
+        assert_eq!(
+            alarm,
+            std::env::current_dir().unwrap().join("sounds/alarm.ogg")
+        );
+    }
+
+    #[test]
+    fn requests_check_the_timer_state() {
+        let mut core = TimerCore::new(Duration::from_secs(60), Default::default());
+
+        assert!(Request::Pause.timer_message(&core).is_err());
+        assert!(Request::AddTime { secs: 60 }.timer_message(&core).is_err());
+        assert!(matches!(
+            Request::Start { duration: None }.timer_message(&core),
+            Ok(Some(TimerMessage::EnableTimer))
+        ));
+
+        core.start();
+        assert!(Request::Start { duration: None }
+            .timer_message(&core)
+            .is_err());
+        assert!(matches!(
+            Request::Pause.timer_message(&core),
+            Ok(Some(TimerMessage::TogglePause))
+        ));
+        assert!(matches!(Request::Resume.timer_message(&core), Ok(None)));
+
+        core.toggle_pause();
+        assert!(matches!(Request::Pause.timer_message(&core), Ok(None)));
+        assert!(matches!(
+            Request::Resume.timer_message(&core),
+            Ok(Some(TimerMessage::TogglePause))
+        ));
+    }
+
+    #[tokio::test]
+    async fn requests_round_trip_over_the_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("timerys").join(SOCKET_FILE_NAME);
+
+        let listener = bind(&path).unwrap();
+        let (sender, mut requests) = mpsc::channel(1);
+        tokio::spawn(serve(listener, sender));
+
+        // Stand in for the app, replying to each request.
+        tokio::spawn(async move {
+            while let Some((request, responder)) = requests.next().await {
+                match request {
+                    Request::Status => responder.respond(Response::ok(status())),
+                    _ => responder.respond(Response::error("nope")),
+                }
+            }
+        });
+
+        let client_path = path.clone();
+        let responses = tokio::task::spawn_blocking(move || {
+            (
+                send_to(&client_path, &Request::Status).unwrap(),
+                send_to(&client_path, &Request::Reset).unwrap(),
+            )
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(responses.0, Response::ok(status()));
+        assert_eq!(responses.1, Response::error("nope"));
+
+        // A second listener shouldn't take over from a live one.
+        assert!(bind(&path).is_err());
+    }
+
+    #[test]
+    fn runtime_dir_must_be_ours() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("timerys").join(SOCKET_FILE_NAME);
+
+        create_parent_dir(&path).unwrap();
+        assert_eq!(
+            fs::metadata(path.parent().unwrap())
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777,
+            0o700
+        );
+
+        // Handing it to someone else needs root.
+        if current_uid() == 0 {
+            std::os::unix::fs::chown(path.parent().unwrap(), Some(65534), None).unwrap();
+            assert!(create_parent_dir(&path).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn socket_is_removed_when_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("timerys").join(SOCKET_FILE_NAME);
+
+        let socket = bind(&path).unwrap();
+        assert!(path.exists());
+
+        drop(socket);
+        assert!(!path.exists());
+    }
+}
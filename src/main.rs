@@ -1,12 +1,33 @@
 //! A simple cross-platform timer app.
 
 mod audio;
+mod cli;
+mod duration_parser;
+mod format;
+mod headless;
+#[cfg(unix)]
+mod instance;
+#[cfg(unix)]
+mod ipc;
+mod notifications;
 mod num_input_container;
+mod recipe;
+mod sequence;
+mod settings;
+mod state;
 mod styling;
+mod synth;
+mod timer;
+mod timer_core;
+mod toml_file;
+#[cfg(feature = "tray")]
+mod tray;
 
 use std::{
-    path::PathBuf,
-    time::{Duration, Instant},
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
 // Q: Why is there this "text as textt" thing?
@@ -14,17 +35,36 @@ use std::{
 // e.g. text::{self, LineHeight} - this then makes calling the "text" function break. Fun!
 // But why not something smarter? Because working with iced is frustrating enough that I don't care
 // as long as it works enough.
+use audio::Tone;
+use clap::Parser;
+use cli::Args;
+use format::{
+    clock_duration, compact_duration, describe_snoozes, parse_duration, parse_overtime,
+    parse_stopwatch_duration, precise_clock_duration, timer_clock,
+};
 use iced::{
     alignment::Horizontal,
-    executor, font, keyboard, theme,
-    widget::{button, column, container, row, text as textt, text::LineHeight},
-    window, Alignment, Application, Command, Element, Font, Length, Settings, Size, Subscription,
-    Theme,
+    clipboard, event, executor, font, keyboard, theme,
+    widget::{
+        button, checkbox, column, container, pick_list, row, scrollable, slider, text as textt,
+        text::LineHeight, text_input, Row,
+    },
+    window, Alignment, Application, Command, Element, Event, Font, Length, Point, Size,
+    Subscription, Theme,
 };
+use notifications::{NotificationAction, NotificationId, Notifier};
 use num_input_container::NumInputContainer;
-use rodio::{OutputStream, OutputStreamHandle, Sink};
-
-use crate::styling::text::{DEFAULT_TEXT_COLOR, DISABLED_TEXT_COLOR};
+use recipe::Recipe;
+use sequence::SequenceConfig;
+use settings::Settings;
+use state::SavedTimers;
+use synth::{SynthConfig, Waveform};
+use timer::{EditingState, Timer, TimerId, TimerMessage};
+use timer_core::{human_duration, IsPaused, RingConfig, TimerAppState, TimerMode, WallClock};
+#[cfg(feature = "tray")]
+use tray::{Tray, TrayAction, TrayStatus};
+
+use crate::styling::text::{DEFAULT_TEXT_COLOR, DISABLED_TEXT_COLOR, ERROR_TEXT_COLOR};
 
 const DEFAULT_FONT: Font = Font {
     family: font::Family::Name("Source Sans 3"),
@@ -44,144 +84,945 @@ const TIME_FONT_SIZE: u16 = 80;
 const UNIT_FONT_SIZE: u16 = 30;
 const BUTTON_FONT_SIZE: u16 = 18;
 
+/// The longest fade-in the slider allows, in seconds.
+const MAX_FADE_IN_SECS: u8 = 30;
+
+/// The longest ring duration the slider allows, in seconds. Anything longer might as well ring until dismissed.
+const MAX_RING_SECS: u16 = 300;
+
+/// The longest repeat interval the slider allows, in minutes.
+const MAX_REPEAT_MINS: u8 = 30;
+
+/// The quick buttons for changing how long a running countdown has left, in seconds.
+const TIME_ADJUSTMENTS: [(&str, i64); 3] = [("+1m", 60), ("+5m", 5 * 60), ("-1m", -60)];
+
+/// The longest snooze the slider allows, in minutes.
+const MAX_SNOOZE_MINS: u8 = 30;
+
+/// The longest work phase the slider allows, in minutes.
+const MAX_WORK_MINS: u8 = 120;
+
+/// The longest break the sliders allow, in minutes.
+const MAX_BREAK_MINS: u8 = 60;
+
+/// The most work phases the slider allows before a long break.
+const MAX_LONG_BREAK_EVERY: u8 = 10;
+
+/// The longest the slider allows a sequence to keep ringing before it moves on by itself, in seconds.
+const MAX_AUTO_ADVANCE_SECS: u8 = 120;
+
 #[derive(Clone, Debug)]
 enum Message {
-    EnableEditTimer,
-    // DisableEditTimer,
-    EditNewNum(u32),
-    EditBackspace,
+    Timer(TimerId, TimerMessage),
     Tick,
-    EnableTimer,
-    TogglePause,
-    ResetTimer,
-    StopRinging,
+    Flash,
+    AddTimer,
+    RemoveTimer(TimerId),
+    SelectTimer(TimerId),
+    CopyLaps(TimerId),
+    ToggleSettings,
+    PickAlarm,
+    AlarmPicked(Option<PathBuf>),
+    ResetAlarm,
+    SetTone(Tone),
+    SetSynth(Option<SynthConfig>),
+    UseAlarmForNewTimers,
+    SetRing(RingConfig),
+    SetSequence(SequenceConfig),
+    EditPresetInput(String),
+    AddPreset,
+    RemovePreset(usize),
+    SetStartOnPreset(bool),
+    LoadRecipe(TimerId, String),
+    EditRecipeName(String),
+    EditRecipeSteps(String),
+    AddRecipe,
+    RemoveRecipe(usize),
+    SetVolume(f32),
+    SetFadeIn(u8),
+    SaveSettings,
+    NotifierConnected(Option<Notifier>),
+    NotificationShown(TimerId, Option<NotificationId>),
+    NotificationClosed,
+    NotificationAction(NotificationId, NotificationAction),
+    #[cfg(feature = "tray")]
+    Tray(TrayAction),
+    #[cfg(unix)]
+    Control(ipc::Request, ipc::Responder),
     FontLoaded(Result<(), font::Error>),
+    WindowMoved {
+        x: i32,
+        y: i32,
+    },
+    WindowResized {
+        width: u32,
+        height: u32,
+    },
+    CloseRequested,
 }
 
-#[derive(Debug)]
-enum IsPaused {
-    Paused { pause_start: Instant },
-    NotPaused,
+/// Lays out the output of [`format::parse_duration`] (or [`format::parse_stopwatch_duration`]) as the big countdown display.
+fn duration_display<'a>(durations: Vec<(String, &'static str)>) -> Row<'a, Message> {
+    let mut displayed_duration = row!().spacing(10);
+    for (amount, unit) in durations {
+        displayed_duration = displayed_duration.push(
+            row!(
+                textt(amount).size(TIME_FONT_SIZE).font(SEMIBOLD_FONT),
+                textt(unit)
+                    .size(UNIT_FONT_SIZE)
+                    .font(SEMIBOLD_FONT)
+                    .line_height(LineHeight::Absolute(TIME_FONT_SIZE.into()))
+            )
+            .align_items(Alignment::End),
+        );
+    }
+
+    displayed_duration
 }
 
-#[derive(Debug)]
-enum TimerAppState {
-    Started {
-        start_instant: Instant,
-        time_left: Duration,
-        total_wait: Duration,
-        is_paused: IsPaused,
-    },
-    Stopped,
-    Ringing,
+/// A setting's label alongside the control for it.
+fn setting_row<'a>(label: String, control: impl Into<Element<'a, Message>>) -> Row<'a, Message> {
+    row![
+        textt(label).size(BUTTON_FONT_SIZE).width(150),
+        control.into()
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center)
 }
 
-#[derive(Clone, Debug)]
-enum EditingState {
-    Editing(String),
-    NotEditing,
+/// A banner explaining why a timer's alarm couldn't be played.
+fn audio_error_banner(err: &str) -> Element<'_, Message> {
+    textt(format!("Couldn't play the alarm: {err}"))
+        .size(BUTTON_FONT_SIZE)
+        .style(theme::Text::Color(ERROR_TEXT_COLOR))
+        .into()
 }
 
-fn human_duration(duration: Duration) -> (u64, u64, u64) {
-    // Ugly way to make it so it doesn't immediately round down to the nearest second.
-    let total_secs_f64 = duration.as_secs_f64();
-    let total_secs = if total_secs_f64 - total_secs_f64.trunc() > 0.1 {
-        total_secs_f64.ceil() as u64
-    } else {
-        total_secs_f64.floor() as u64
-    };
+/// Closes a notification in the background, as there's nothing to do once it's gone.
+fn close_notification(notifier: Notifier, id: NotificationId) -> Command<Message> {
+    Command::perform(notifier.close(id), |result| {
+        if let Err(err) = result {
+            eprintln!("Failed to close a notification: {err:?}");
+        }
 
-    let hours = total_secs / (60 * 60);
-    let minutes = (total_secs % (60 * 60)) / 60;
-    let seconds = total_secs % 60;
+        Message::NotificationClosed
+    })
+}
 
-    (hours, minutes, seconds)
+/// The name of a file, for showing in the UI.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
 }
 
-fn parse_duration(duration: Duration) -> Vec<(String, &'static str)> {
-    let (hours, minutes, seconds) = human_duration(duration);
+/// What the app is launched with.
+struct AppFlags {
+    settings: Settings,
+    args: Args,
 
-    let mut ret = vec![];
+    /// Whether this is the only window, in which case it takes requests from the control socket.
+    #[cfg(unix)]
+    is_primary: bool,
+}
 
-    if hours > 0 {
-        ret.push((format!("{hours}"), "h"));
-        ret.push((format!("{minutes:0>2}"), "m"));
-        ret.push((format!("{seconds:0>2}"), "s"));
-    } else if minutes > 0 {
-        ret.push((format!("{minutes}"), "m"));
-        ret.push((format!("{seconds:0>2}"), "s"));
-    } else {
-        ret.push((format!("{seconds}"), "s"));
-    }
+struct TimerApp {
+    timers: Vec<Timer>,
+    selected: TimerId,
+    next_id: TimerId,
+    settings: Settings,
+
+    /// Whether the settings panel is shown in place of the timer.
+    show_settings: bool,
 
-    ret
+    /// Why the last alarm sound that was picked couldn't be used, if it couldn't.
+    alarm_error: Option<String>,
+
+    /// Which half of a flash a visual alarm is in.
+    flash_on: bool,
+
+    /// What's been typed in as a new preset so far.
+    preset_input: String,
+
+    /// Why the preset that was typed in couldn't be added, if it couldn't.
+    preset_error: Option<String>,
+
+    /// What's been typed in as a new recipe so far.
+    recipe_name_input: String,
+    recipe_steps_input: String,
+
+    /// Why the recipe that was typed in couldn't be added, if it couldn't.
+    recipe_error: Option<String>,
+
+    /// The desktop notification service, once it's been connected to.
+    notifier: Option<Notifier>,
+
+    /// The notification shown for each ringing timer. The ID is `None` until the service has replied with it.
+    notifications: HashMap<TimerId, Option<NotificationId>>,
+
+    /// The system tray icon, if one could be created.
+    #[cfg(feature = "tray")]
+    tray: Option<Tray>,
+
+    #[cfg(unix)]
+    is_primary: bool,
+
+    /// For converting the timers' progress to wall-clock time when saving them.
+    wall_clock: WallClock,
+
+    /// What was last written to the state file, so it's only written again when something changes.
+    saved_timers: SavedTimers,
 }
 
-// TODO: 2x calls to this are probably unnecessary, can dedupe it.
-fn string_to_hms(s: &str) -> (Option<u32>, Option<u32>, Option<u32>) {
-    let mut iter = s.chars();
+impl TimerApp {
+    fn add_timer(&mut self) -> TimerId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.timers.push(Timer::new(id, &self.settings));
+
+        id
+    }
 
-    let seconds = if let Some(c) = iter.next_back() {
-        let mut num = 0;
-        num += c.to_digit(10).unwrap();
+    /// Applies command-line arguments to a timer.
+    fn apply_args(&mut self, id: TimerId, args: &Args) {
+        let Some(timer) = self.timers.iter_mut().find(|timer| timer.id == id) else {
+            return;
+        };
 
-        if let Some(c) = iter.next_back() {
-            num += c.to_digit(10).unwrap() * 10;
+        if let Some(duration) = args.duration {
+            timer.core.to_wait = duration;
         }
 
-        Some(num)
-    } else {
-        None
-    };
+        if let Some(alarm) = &args.alarm {
+            timer.alarm.path = Some(alarm.clone());
+        }
 
-    let minutes = if let Some(c) = iter.next_back() {
-        let mut num = 0;
-        num += c.to_digit(10).unwrap();
+        if let Some(label) = &args.label {
+            timer.label = label.clone();
+        }
 
-        if let Some(c) = iter.next_back() {
-            num += c.to_digit(10).unwrap() * 10;
+        if let Some(volume) = args.volume {
+            timer.alarm.volume = volume;
         }
 
-        Some(num)
-    } else {
-        None
-    };
+        if args.start {
+            timer.update(TimerMessage::EnableTimer);
+        }
+    }
+
+    fn selected_index(&self) -> usize {
+        self.timers
+            .iter()
+            .position(|timer| timer.id == self.selected)
+            .unwrap_or(0)
+    }
 
-    let hours = if let Some(c) = iter.next() {
-        let mut num = 0;
-        num += c.to_digit(10).unwrap();
+    fn selected_timer(&self) -> &Timer {
+        // There is always at least one timer, and the selected ID always refers to one of them.
+        self.timers
+            .iter()
+            .find(|timer| timer.id == self.selected)
+            .unwrap_or(&self.timers[0])
+    }
 
-        while let Some(c) = iter.next_back() {
-            num *= 10;
-            num += c.to_digit(10).unwrap();
+    /// The name of a timer as shown in the UI, falling back to its position if it has no label.
+    fn timer_name(&self, index: usize) -> String {
+        let label = self.timers[index].label.trim();
+        if label.is_empty() {
+            format!("Timer {}", index + 1)
+        } else {
+            label.to_string()
         }
+    }
 
-        Some(num)
-    } else {
-        None
-    };
+    /// Plays a bit of the selected timer's alarm.
+    fn preview_alarm(&mut self) {
+        let id = self.selected;
+        if let Some(timer) = self.timers.iter_mut().find(|timer| timer.id == id) {
+            timer.update(TimerMessage::PreviewAlarm);
+        }
+    }
 
-    (hours, minutes, seconds)
-}
+    fn selected_timer_mut(&mut self) -> &mut Timer {
+        let index = self.selected_index();
+        &mut self.timers[index]
+    }
 
-struct TimerApp {
-    state: TimerAppState,
-    is_editing: EditingState,
+    /// The settings panel, shown in place of the timer.
+    fn settings_view(&self) -> Element<'_, Message> {
+        let timer = self.selected_timer();
+        let id = timer.id;
 
-    // TODO: Things to be loaded from settings/state.
-    to_wait: Duration,
-    alarm_path: Option<PathBuf>,
-    alarm_stream: Option<(OutputStream, OutputStreamHandle, Sink)>,
-}
+        let mut content = column![textt("Settings").size(UNIT_FONT_SIZE)]
+            .align_items(Alignment::Center)
+            .spacing(20)
+            .max_width(600);
 
-impl TimerApp {
-    fn update_to_wait_from_str(&mut self, s: &str) {
-        let (hours, minutes, seconds) = string_to_hms(s);
+        let alarm_name = match &timer.alarm.path {
+            Some(path) => file_name(path),
+            None => "None".to_string(),
+        };
+
+        // The sound, volume and fade-in are picked per timer. New timers start off with the defaults, which have to be
+        // set explicitly.
+        let is_default_alarm = timer.alarm.is_default(&self.settings);
+
+        content = content.push(
+            row![
+                textt(format!(
+                    "Alarm for {}",
+                    self.timer_name(self.selected_index())
+                ))
+                .size(BUTTON_FONT_SIZE),
+                button(textt("Use for new timers").size(BUTTON_FONT_SIZE))
+                    .padding(6)
+                    .style(theme::Button::Secondary)
+                    .on_press_maybe((!is_default_alarm).then_some(Message::UseAlarmForNewTimers)),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        );
+
+        content = content.push(
+            row![
+                textt("Tone").size(BUTTON_FONT_SIZE),
+                pick_list(&Tone::ALL[..], Some(timer.alarm.tone), Message::SetTone)
+                    .text_size(BUTTON_FONT_SIZE),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        );
+
+        content = content.push(
+            row![
+                textt(format!("Custom sound: {alarm_name}")).size(BUTTON_FONT_SIZE),
+                button(textt("Choose...").size(BUTTON_FONT_SIZE))
+                    .padding(6)
+                    .style(theme::Button::Secondary)
+                    .on_press(Message::PickAlarm),
+                button(textt("Clear").size(BUTTON_FONT_SIZE))
+                    .padding(6)
+                    .style(theme::Button::Secondary)
+                    .on_press_maybe(timer.alarm.path.is_some().then_some(Message::ResetAlarm)),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        );
+
+        if let Some(err) = &self.alarm_error {
+            content = content.push(
+                textt(err)
+                    .size(BUTTON_FONT_SIZE)
+                    .style(theme::Text::Color(ERROR_TEXT_COLOR)),
+            );
+        }
+
+        if let Some(err) = &timer.audio_error {
+            content = content.push(audio_error_banner(err));
+        }
+
+        let mut volume_row = row![
+            textt("Volume").size(BUTTON_FONT_SIZE),
+            slider(0.0..=1.0, timer.alarm.volume, Message::SetVolume)
+                .step(0.01)
+                .width(150),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        if timer.core.is_stopped() {
+            volume_row = volume_row.push(
+                button(textt("Preview").size(BUTTON_FONT_SIZE))
+                    .padding(6)
+                    .style(theme::Button::Secondary)
+                    .on_press(Message::Timer(id, TimerMessage::PreviewAlarm)),
+            );
+        }
+
+        let fade_in_secs = timer.alarm.fade_in.map_or(0, |fade_in| {
+            fade_in.as_secs().min(MAX_FADE_IN_SECS.into()) as u8
+        });
+        let fade_in_label = if fade_in_secs == 0 {
+            "Fade in: off".to_string()
+        } else {
+            format!("Fade in: {fade_in_secs}s")
+        };
+
+        content = content.push(volume_row).push(
+            row![
+                textt(fade_in_label).size(BUTTON_FONT_SIZE).width(110),
+                slider(0..=MAX_FADE_IN_SECS, fade_in_secs, Message::SetFadeIn).width(150),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        );
+
+        let mut presets = row![textt("Presets").size(BUTTON_FONT_SIZE)]
+            .spacing(10)
+            .align_items(Alignment::Center);
+        for (index, &duration) in self.settings.presets.iter().enumerate() {
+            presets = presets.push(
+                button(textt(format!("{} ×", compact_duration(duration))).size(BUTTON_FONT_SIZE))
+                    .padding(6)
+                    .style(theme::Button::Secondary)
+                    .on_press(Message::RemovePreset(index)),
+            );
+        }
+
+        content = content
+            .push(
+                scrollable(presets).direction(scrollable::Direction::Horizontal(
+                    scrollable::Properties::default(),
+                )),
+            )
+            .push(
+                row![
+                    text_input("e.g. 15m or 1h 30m", &self.preset_input)
+                        .on_input(Message::EditPresetInput)
+                        .on_submit(Message::AddPreset)
+                        .size(BUTTON_FONT_SIZE)
+                        .width(200),
+                    button(textt("Add").size(BUTTON_FONT_SIZE))
+                        .padding(6)
+                        .style(theme::Button::Secondary)
+                        .on_press(Message::AddPreset),
+                ]
+                .spacing(10)
+                .align_items(Alignment::Center),
+            );
+
+        if let Some(err) = &self.preset_error {
+            content = content.push(
+                textt(err)
+                    .size(BUTTON_FONT_SIZE)
+                    .style(theme::Text::Color(ERROR_TEXT_COLOR)),
+            );
+        }
 
-        self.to_wait = Duration::from_secs(
-            (hours.unwrap_or(0) * 60 * 60 + minutes.unwrap_or(0) * 60 + seconds.unwrap_or(0))
-                .into(),
+        content = content.push(
+            checkbox(
+                "Start when a preset is picked",
+                self.settings.start_on_preset,
+            )
+            .text_size(BUTTON_FONT_SIZE)
+            .on_toggle(Message::SetStartOnPreset),
+        );
+
+        let mut recipes = column![textt("Recipes").size(BUTTON_FONT_SIZE)]
+            .spacing(6)
+            .align_items(Alignment::Center);
+        for (index, recipe) in self.settings.recipes.iter().enumerate() {
+            let steps: Vec<String> = recipe.steps.iter().copied().map(compact_duration).collect();
+            recipes = recipes.push(
+                button(
+                    textt(format!("{}: {} ×", recipe.name, steps.join(", ")))
+                        .size(BUTTON_FONT_SIZE),
+                )
+                .padding(6)
+                .style(theme::Button::Secondary)
+                .on_press(Message::RemoveRecipe(index)),
+            );
+        }
+
+        content = content.push(recipes).push(
+            row![
+                text_input("Name", &self.recipe_name_input)
+                    .on_input(Message::EditRecipeName)
+                    .size(BUTTON_FONT_SIZE)
+                    .width(120),
+                text_input("e.g. 30m, 45m, 20m", &self.recipe_steps_input)
+                    .on_input(Message::EditRecipeSteps)
+                    .on_submit(Message::AddRecipe)
+                    .size(BUTTON_FONT_SIZE)
+                    .width(180),
+                button(textt("Add").size(BUTTON_FONT_SIZE))
+                    .padding(6)
+                    .style(theme::Button::Secondary)
+                    .on_press(Message::AddRecipe),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
         );
+
+        if let Some(err) = &self.recipe_error {
+            content = content.push(
+                textt(err)
+                    .size(BUTTON_FONT_SIZE)
+                    .style(theme::Text::Color(ERROR_TEXT_COLOR)),
+            );
+        }
+
+        let ring = self.settings.ring.clone();
+        let ring_label = if ring.duration.is_zero() {
+            "Ring until dismissed".to_string()
+        } else {
+            format!("Ring for {}s", ring.duration.as_secs())
+        };
+        let repeat_mins = ring.repeat.map_or(0, |repeat| repeat.as_secs() / 60);
+        let snooze_mins = ring.snooze.as_secs() / 60;
+        let repeat_label = if repeat_mins == 0 {
+            "Don't repeat".to_string()
+        } else if ring.repeat().is_none() {
+            // The repeat has to be longer than the ring for the alarm to ever pause.
+            format!("Repeat every {repeat_mins}m (must exceed the ring)")
+        } else {
+            format!("Repeat every {repeat_mins}m")
+        };
+
+        content = content
+            .push(setting_row(
+                ring_label,
+                slider(
+                    0..=MAX_RING_SECS,
+                    ring.duration.as_secs().min(MAX_RING_SECS.into()) as u16,
+                    {
+                        let ring = ring.clone();
+                        move |secs| {
+                            Message::SetRing(RingConfig {
+                                duration: Duration::from_secs(secs.into()),
+                                ..ring.clone()
+                            })
+                        }
+                    },
+                )
+                .step(5u16)
+                .on_release(Message::SaveSettings),
+            ))
+            .push(setting_row(
+                repeat_label,
+                slider(
+                    0..=MAX_REPEAT_MINS,
+                    repeat_mins.min(MAX_REPEAT_MINS.into()) as u8,
+                    {
+                        let ring = ring.clone();
+                        move |mins| {
+                            Message::SetRing(RingConfig {
+                                repeat: (mins > 0)
+                                    .then(|| Duration::from_secs(u64::from(mins) * 60)),
+                                ..ring.clone()
+                            })
+                        }
+                    },
+                )
+                .on_release(Message::SaveSettings),
+            ))
+            .push(setting_row(
+                format!("Snooze for {snooze_mins}m"),
+                slider(
+                    1..=MAX_SNOOZE_MINS,
+                    snooze_mins.clamp(1, MAX_SNOOZE_MINS.into()) as u8,
+                    move |mins| {
+                        Message::SetRing(RingConfig {
+                            snooze: Duration::from_secs(u64::from(mins) * 60),
+                            ..ring.clone()
+                        })
+                    },
+                )
+                .on_release(Message::SaveSettings),
+            ));
+
+        content = content.push(
+            checkbox(
+                "Keep counting overtime after dismissing",
+                self.settings.ring.overtime_after_dismiss,
+            )
+            .text_size(BUTTON_FONT_SIZE)
+            .on_toggle(|overtime_after_dismiss| {
+                Message::SetRing(RingConfig {
+                    overtime_after_dismiss,
+                    ..self.settings.ring.clone()
+                })
+            }),
+        );
+
+        let sequence = self.settings.sequence.clone();
+        let minutes_slider =
+            |max: u8,
+             duration: Duration,
+             on_change: fn(SequenceConfig, Duration) -> SequenceConfig| {
+                let sequence = sequence.clone();
+                slider(
+                    1..=max,
+                    (duration.as_secs() / 60).clamp(1, max.into()) as u8,
+                    move |mins| {
+                        Message::SetSequence(on_change(
+                            sequence.clone(),
+                            Duration::from_secs(u64::from(mins) * 60),
+                        ))
+                    },
+                )
+                .on_release(Message::SaveSettings)
+            };
+        let auto_advance_secs = sequence
+            .auto_advance
+            .map_or(0, |auto_advance| auto_advance.as_secs());
+        let auto_advance_label = if auto_advance_secs == 0 {
+            "Wait to move on".to_string()
+        } else {
+            format!("Move on after {auto_advance_secs}s")
+        };
+
+        content = content
+            .push(setting_row(
+                format!("Work for {}", compact_duration(sequence.work)),
+                minutes_slider(MAX_WORK_MINS, sequence.work, |sequence, work| {
+                    SequenceConfig { work, ..sequence }
+                }),
+            ))
+            .push(setting_row(
+                format!("Short breaks of {}", compact_duration(sequence.short_break)),
+                minutes_slider(
+                    MAX_BREAK_MINS,
+                    sequence.short_break,
+                    |sequence, short_break| SequenceConfig {
+                        short_break,
+                        ..sequence
+                    },
+                ),
+            ))
+            .push(setting_row(
+                format!("Long breaks of {}", compact_duration(sequence.long_break)),
+                minutes_slider(
+                    MAX_BREAK_MINS,
+                    sequence.long_break,
+                    |sequence, long_break| SequenceConfig {
+                        long_break,
+                        ..sequence
+                    },
+                ),
+            ))
+            .push(setting_row(
+                format!("Long break every {}", sequence.long_break_every),
+                slider(
+                    1..=MAX_LONG_BREAK_EVERY,
+                    sequence
+                        .long_break_every
+                        .clamp(1, MAX_LONG_BREAK_EVERY.into()) as u8,
+                    {
+                        let sequence = sequence.clone();
+                        move |long_break_every| {
+                            Message::SetSequence(SequenceConfig {
+                                long_break_every: long_break_every.into(),
+                                ..sequence.clone()
+                            })
+                        }
+                    },
+                )
+                .on_release(Message::SaveSettings),
+            ))
+            .push(setting_row(
+                auto_advance_label,
+                slider(
+                    0..=MAX_AUTO_ADVANCE_SECS,
+                    auto_advance_secs.min(MAX_AUTO_ADVANCE_SECS.into()) as u8,
+                    move |secs| {
+                        Message::SetSequence(SequenceConfig {
+                            auto_advance: (secs > 0).then(|| Duration::from_secs(secs.into())),
+                            ..sequence.clone()
+                        })
+                    },
+                )
+                .step(5u8)
+                .on_release(Message::SaveSettings),
+            ));
+
+        let synth = timer.alarm.synth.clone();
+        content = content.push(
+            checkbox("Synthesized alarm", synth.is_some())
+                .text_size(BUTTON_FONT_SIZE)
+                .on_toggle(|enabled| Message::SetSynth(enabled.then(SynthConfig::default))),
+        );
+
+        if let Some(config) = synth {
+            content = content
+                .push(setting_row(
+                    format!("Pitch: {} Hz", config.pitch.round()),
+                    slider(100.0..=2000.0, config.pitch, {
+                        let config = config.clone();
+                        move |pitch| {
+                            Message::SetSynth(Some(SynthConfig {
+                                pitch,
+                                ..config.clone()
+                            }))
+                        }
+                    })
+                    .step(10.0),
+                ))
+                .push(setting_row(
+                    "Waveform".to_string(),
+                    pick_list(&Waveform::ALL[..], Some(config.waveform), {
+                        let config = config.clone();
+                        move |waveform| {
+                            Message::SetSynth(Some(SynthConfig {
+                                waveform,
+                                ..config.clone()
+                            }))
+                        }
+                    })
+                    .text_size(BUTTON_FONT_SIZE),
+                ))
+                .push(setting_row(
+                    format!("Beeps: {}", config.beeps),
+                    slider(1..=8, config.beeps, {
+                        let config = config.clone();
+                        move |beeps| {
+                            Message::SetSynth(Some(SynthConfig {
+                                beeps,
+                                ..config.clone()
+                            }))
+                        }
+                    }),
+                ))
+                .push(setting_row(
+                    format!("Beep length: {}ms", config.beep_length.as_millis()),
+                    slider(50..=1000, config.beep_length.as_millis() as u32, {
+                        let config = config.clone();
+                        move |millis| {
+                            Message::SetSynth(Some(SynthConfig {
+                                beep_length: Duration::from_millis(millis.into()),
+                                ..config.clone()
+                            }))
+                        }
+                    })
+                    .step(10u32),
+                ))
+                .push(setting_row(
+                    format!("Gap: {}ms", config.gap.as_millis()),
+                    slider(0..=1000, config.gap.as_millis() as u32, {
+                        let config = config.clone();
+                        move |millis| {
+                            Message::SetSynth(Some(SynthConfig {
+                                gap: Duration::from_millis(millis.into()),
+                                ..config.clone()
+                            }))
+                        }
+                    })
+                    .step(10u32),
+                ))
+                .push(setting_row(
+                    format!("Every {:.1}s", config.period.as_secs_f32()),
+                    slider(500..=10000, config.period.as_millis() as u32, {
+                        let config = config.clone();
+                        move |millis| {
+                            Message::SetSynth(Some(SynthConfig {
+                                period: Duration::from_millis(millis.into()),
+                                ..config.clone()
+                            }))
+                        }
+                    })
+                    .step(100u32),
+                ));
+        }
+
+        content = content.push(
+            button(
+                textt("Done")
+                    .size(BUTTON_FONT_SIZE)
+                    .horizontal_alignment(Horizontal::Center),
+            )
+            .width(90)
+            .padding(10)
+            .style(theme::Button::Primary)
+            .on_press(Message::ToggleSettings),
+        );
+
+        // There are enough settings that they might not all fit.
+        container(scrollable(
+            container(content)
+                .width(Length::Fill)
+                .center_x()
+                .padding(20),
+        ))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_y()
+        .into()
+    }
+
+    /// Whether this window is the one that saves and restores the running timers. With more than one window open,
+    /// only the first one does, so they don't overwrite each other.
+    fn persists_timers(&self) -> bool {
+        #[cfg(unix)]
+        return self.is_primary;
+
+        #[cfg(not(unix))]
+        true
+    }
+
+    /// Picks up the timers that were running when the app last closed.
+    fn restore_timers(&mut self) {
+        let saved = match SavedTimers::load() {
+            Ok(saved) => saved,
+            Err(err) => {
+                eprintln!("Failed to load saved timers: {err:?}");
+                return;
+            }
+        };
+
+        for saved_timer in &saved.timers {
+            let id = self.next_id;
+            self.next_id += 1;
+
+            self.timers.push(Timer::restore(
+                id,
+                saved_timer,
+                &self.settings,
+                self.wall_clock,
+            ));
+        }
+
+        if let Some(timer) = saved.selected.and_then(|index| self.timers.get(index)) {
+            self.selected = timer.id;
+        } else if let Some(timer) = self.timers.first() {
+            self.selected = timer.id;
+        }
+
+        self.saved_timers = saved;
+    }
+
+    /// Saves the running timers, if anything's changed since they were last saved.
+    fn save_timers(&mut self) {
+        if !self.persists_timers() {
+            return;
+        }
+
+        self.wall_clock.resync();
+
+        let mut saved = SavedTimers::default();
+        for timer in &self.timers {
+            if let Some(saved_timer) = timer.save(self.wall_clock) {
+                if timer.id == self.selected {
+                    saved.selected = Some(saved.timers.len());
+                }
+                saved.timers.push(saved_timer);
+            }
+        }
+
+        if saved == self.saved_timers {
+            return;
+        }
+
+        if let Err(err) = saved.save() {
+            eprintln!("Failed to save timers: {err:?}");
+        }
+        self.saved_timers = saved;
+    }
+
+    /// Brings everything outside the window that mirrors the timers up to date.
+    fn sync_status(&mut self) -> Command<Message> {
+        self.save_timers();
+
+        #[cfg(feature = "tray")]
+        self.sync_tray();
+
+        self.sync_notifications()
+    }
+
+    #[cfg(feature = "tray")]
+    fn sync_tray(&mut self) {
+        let Some(is_window_hidden) = self.tray.as_ref().map(Tray::is_window_hidden) else {
+            return;
+        };
+
+        let core = &self.selected_timer().core;
+        let is_paused = matches!(
+            core.state,
+            TimerAppState::Started {
+                is_paused: IsPaused::Paused { .. },
+                ..
+            }
+        );
+        let status = TrayStatus {
+            title: self.title(),
+            clock: timer_clock(core),
+            can_start: core.is_stopped(),
+            can_pause: core.is_started(),
+            is_paused,
+            can_reset: !core.is_stopped(),
+            can_stop_ringing: core.is_unacknowledged(),
+            is_window_hidden,
+        };
+
+        if let Some(tray) = &mut self.tray {
+            tray.set_status(status);
+        }
+    }
+
+    /// The selected timer's status, for replying to the control socket.
+    #[cfg(unix)]
+    fn control_status(&self) -> ipc::Status {
+        let index = self.selected_index();
+        let core = &self.timers[index].core;
+
+        ipc::Status {
+            name: self.timer_name(index),
+            mode: core.mode,
+            state: ipc::TimerState::of(core),
+            clock: timer_clock(core),
+            remaining_secs: core.displayed_duration().as_secs(),
+            overtime_secs: core.overtime().map(|overtime| overtime.as_secs()),
+        }
+    }
+
+    /// Shows a notification for each timer that's started ringing, and closes the ones for timers that have been
+    /// dealt with (or removed) since.
+    fn sync_notifications(&mut self) -> Command<Message> {
+        let Some(notifier) = &self.notifier else {
+            return Command::none();
+        };
+
+        let mut commands = vec![];
+
+        let timers = &self.timers;
+        self.notifications.retain(|timer_id, notification| {
+            let is_unacknowledged = timers
+                .iter()
+                .any(|timer| timer.id == *timer_id && timer.core.is_unacknowledged());
+
+            if !is_unacknowledged {
+                if let Some(id) = *notification {
+                    commands.push(close_notification(notifier.clone(), id));
+                }
+            }
+
+            is_unacknowledged
+        });
+
+        for (index, timer) in self.timers.iter().enumerate() {
+            if !timer.core.is_unacknowledged() || self.notifications.contains_key(&timer.id) {
+                continue;
+            }
+
+            let mut body = String::from("Time's up!");
+            if timer.core.mode == TimerMode::Sequence {
+                body.push_str(&format!(" {} is over.", timer.core.sequence.describe()));
+            }
+            if let Some(step) = timer.core.describe_step() {
+                body.push_str(&format!(" ({step})"));
+            }
+
+            let timer_id = timer.id;
+            self.notifications.insert(timer_id, None);
+            commands.push(Command::perform(
+                notifier.clone().show(self.timer_name(index), body),
+                move |result| {
+                    let id = result
+                        .map_err(|err| eprintln!("Failed to show a notification: {err:?}"))
+                        .ok();
+                    Message::NotificationShown(timer_id, id)
+                },
+            ));
+        }
+
+        Command::batch(commands)
+    }
+
+    fn save_settings(&self) {
+        if let Err(err) = self.settings.save() {
+            eprintln!("Failed to save settings: {err:?}");
+        }
     }
 }
 
@@ -192,175 +1033,644 @@ impl Application for TimerApp {
 
     type Theme = Theme;
 
-    type Flags = ();
-
-    fn new(_flags: Self::Flags) -> (Self, Command<Self::Message>) {
-        let app = TimerApp {
-            state: TimerAppState::Stopped {},
-            is_editing: EditingState::NotEditing,
-            to_wait: Duration::from_secs(5 * 60), // Default to 5 minutes
-            alarm_path: None,
-            alarm_stream: None,
+    type Flags = AppFlags;
+
+    fn new(flags: Self::Flags) -> (Self, Command<Self::Message>) {
+        let AppFlags {
+            settings,
+            args,
+            #[cfg(unix)]
+            is_primary,
+        } = flags;
+
+        let mut app = TimerApp {
+            timers: vec![],
+            selected: 0,
+            next_id: 0,
+            settings,
+            show_settings: false,
+            alarm_error: None,
+            flash_on: false,
+            preset_input: String::new(),
+            preset_error: None,
+            recipe_name_input: String::new(),
+            recipe_steps_input: String::new(),
+            recipe_error: None,
+            notifier: None,
+            notifications: HashMap::new(),
+            #[cfg(feature = "tray")]
+            tray: Tray::new()
+                .map_err(|err| eprintln!("Failed to create the tray icon: {err:?}"))
+                .ok(),
+            #[cfg(unix)]
+            is_primary,
+            wall_clock: WallClock::now(),
+            saved_timers: SavedTimers::default(),
         };
 
-        let command = Command::batch(vec![
+        if app.persists_timers() {
+            app.restore_timers();
+        }
+
+        // Restored timers are left as they were, so anything asked for on the command line gets a new one.
+        if app.timers.is_empty() || args.sets_up_timer() {
+            app.selected = app.add_timer();
+        }
+        app.apply_args(app.selected, &args);
+
+        // There's only something to add to this on Linux, for now.
+        #[cfg_attr(not(target_os = "linux"), allow(unused_mut))]
+        let mut commands = vec![
             font::load(include_bytes!("../assets/fonts/SourceSans3-Regular.ttf").as_slice())
                 .map(Message::FontLoaded),
             font::load(include_bytes!("../assets/fonts/SourceSans3-SemiBold.ttf").as_slice())
                 .map(Message::FontLoaded),
-        ]);
+        ];
+
+        #[cfg(target_os = "linux")]
+        commands.push(Command::perform(
+            async {
+                Notifier::connect()
+                    .await
+                    .map_err(|err| {
+                        eprintln!("Failed to connect to the notification service: {err:?}")
+                    })
+                    .ok()
+            },
+            Message::NotifierConnected,
+        ));
 
-        (app, command)
+        (app, Command::batch(commands))
     }
 
     fn title(&self) -> String {
-        let duration = match self.state {
-            TimerAppState::Started { time_left, .. } => time_left,
-            _ => self.to_wait,
-        };
+        let timer = self.selected_timer();
+        let mut title = String::from("Timerys");
 
-        let (hours, minutes, seconds) = human_duration(duration);
-        let title_time = if hours > 0 {
-            format!("{hours:0>2}:{minutes:0>2}:{seconds:0>2}")
-        } else {
-            format!("{minutes:0>2}:{seconds:0>2}")
-        };
+        let label = timer.label.trim();
+        if !label.is_empty() {
+            title.push_str(&format!(" - {label}"));
+        }
 
-        format!("Timerys - {title_time}",)
+        if timer.core.mode == TimerMode::Sequence {
+            title.push_str(&format!(" - {}", timer.core.sequence.describe()));
+        }
+
+        if let Some(step) = timer.core.describe_step() {
+            title.push_str(&format!(" - {step}"));
+        }
+
+        if let Some(snoozed) = describe_snoozes(timer.core.snoozes) {
+            title.push_str(&format!(" - {snoozed}"));
+        }
+
+        title.push_str(&format!(" - {}", timer_clock(&timer.core)));
+
+        title
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
         match message {
-            Message::FontLoaded(res) => {
-                match res {
-                    Ok(_) => {}
+            Message::FontLoaded(res) => match res {
+                Ok(_) => {}
+                Err(err) => {
+                    println!("Failed to load font: {err:?}");
+                }
+            },
+            Message::WindowMoved { x, y } => {
+                self.settings.window.x = Some(x);
+                self.settings.window.y = Some(y);
+            }
+            Message::WindowResized { width, height } => {
+                self.settings.window.width = width as f32;
+                self.settings.window.height = height as f32;
+            }
+            Message::CloseRequested => {
+                self.save_settings();
+                self.save_timers();
+                return window::close(window::Id::MAIN);
+            }
+            Message::Tick => {
+                for timer in &mut self.timers {
+                    timer.update(TimerMessage::Tick);
+                }
+
+                return self.sync_status();
+            }
+            Message::Flash => {
+                self.flash_on = !self.flash_on;
+
+                // Ring the terminal bell too, in case the window isn't visible.
+                if self.flash_on {
+                    print!("\x07");
+                    let _ = std::io::stdout().flush();
+                }
+            }
+            Message::AddTimer => {
+                self.selected = self.add_timer();
+                return self.sync_status();
+            }
+            Message::RemoveTimer(id) => {
+                if self.timers.len() > 1 {
+                    if let Some(index) = self.timers.iter().position(|timer| timer.id == id) {
+                        let mut timer = self.timers.remove(index);
+                        timer.alarm.stop();
+
+                        if self.selected == id {
+                            self.selected = self.timers[index.min(self.timers.len() - 1)].id;
+                        }
+                    }
+                }
+
+                return self.sync_status();
+            }
+            Message::SelectTimer(id) => {
+                self.selected = id;
+                return self.sync_status();
+            }
+            Message::CopyLaps(id) => {
+                if let Some(timer) = self.timers.iter().find(|timer| timer.id == id) {
+                    return clipboard::write(timer.core.laps_csv());
+                }
+            }
+            Message::ToggleSettings => {
+                self.show_settings = !self.show_settings;
+            }
+            Message::PickAlarm => {
+                return Command::perform(
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Choose an alarm sound")
+                        .add_filter("Audio", &["ogg", "mp3", "wav", "flac"])
+                        .pick_file(),
+                    |file| Message::AlarmPicked(file.map(|file| file.path().to_path_buf())),
+                );
+            }
+            Message::AlarmPicked(path) => {
+                // No path means the dialog was cancelled.
+                let Some(path) = path else {
+                    return Command::none();
+                };
+
+                match audio::validate(&path) {
+                    Ok(()) => {
+                        self.alarm_error = None;
+                        self.selected_timer_mut().alarm.path = Some(path);
+
+                        // Play a bit of it so it's clear what was picked.
+                        self.preview_alarm();
+                    }
                     Err(err) => {
-                        println!("Failed to load font: {err:?}");
+                        self.alarm_error =
+                            Some(format!("Couldn't use {}: {err}", file_name(&path)));
                     }
                 }
+            }
+            Message::ResetAlarm => {
+                self.alarm_error = None;
+                self.selected_timer_mut().alarm.path = None;
+            }
+            Message::SetTone(tone) => {
+                // Other sounds would take priority, so picking a tone means not using them anymore.
+                let alarm = &mut self.selected_timer_mut().alarm;
+                alarm.tone = tone;
+                alarm.synth = None;
+                alarm.path = None;
+
+                self.alarm_error = None;
+                self.preview_alarm();
+            }
+            Message::SetSynth(synth) => {
+                self.selected_timer_mut().alarm.synth = synth;
+            }
+            Message::UseAlarmForNewTimers => {
+                let alarm = &self.selected_timer().alarm;
+                let (path, tone, synth) = (alarm.path.clone(), alarm.tone, alarm.synth.clone());
+                let (volume, fade_in) = (alarm.volume, alarm.fade_in);
+
+                self.settings.alarm_path = path;
+                self.settings.tone = tone;
+                self.settings.synth = synth;
+                self.settings.volume = volume;
+                self.settings.fade_in = fade_in;
+                self.save_settings();
+            }
+            Message::SetRing(ring) => {
+                for timer in &mut self.timers {
+                    timer.core.ring = ring.clone();
+                }
+
+                // Sliders save once released, but checkboxes don't have an equivalent.
+                let toggled =
+                    self.settings.ring.overtime_after_dismiss != ring.overtime_after_dismiss;
+                self.settings.ring = ring;
 
-                return Command::none();
+                if toggled {
+                    self.save_settings();
+                }
             }
-            Message::ResetTimer => {
-                self.state = TimerAppState::Stopped;
-                self.stop_audio();
-                return Command::none();
+            Message::SetSequence(sequence) => {
+                // Timers already counting down keep the length they started with.
+                for timer in &mut self.timers {
+                    timer.core.sequence.config = sequence.clone();
+                }
+
+                self.settings.sequence = sequence;
             }
-            _ => (),
-        }
+            Message::EditPresetInput(input) => {
+                self.preset_input = input;
+                self.preset_error = None;
+            }
+            Message::AddPreset => match duration_parser::parse(&self.preset_input) {
+                Ok(duration) if duration.is_zero() => {
+                    self.preset_error = Some("Presets can't be zero".to_string());
+                }
+                Ok(duration) => {
+                    if !self.settings.presets.contains(&duration) {
+                        self.settings.presets.push(duration);
+                        self.settings.presets.sort();
+                        self.save_settings();
+                    }
 
-        match &mut self.state {
-            TimerAppState::Stopped => match message {
-                Message::EditNewNum(new_digit) => {
-                    let current = match &mut self.is_editing {
-                        EditingState::Editing(old_state) => {
-                            // TODO: For now, limit to 6 digits, can support more in the future.
-                            if old_state.len() >= 6 {
-                                return Command::none();
+                    self.preset_input.clear();
+                    self.preset_error = None;
+                }
+                Err(err) => {
+                    self.preset_error = Some(format!("Couldn't add that preset: {err}"));
+                }
+            },
+            Message::RemovePreset(index) => {
+                if index < self.settings.presets.len() {
+                    self.settings.presets.remove(index);
+                    self.save_settings();
+                }
+            }
+            Message::SetStartOnPreset(start_on_preset) => {
+                self.settings.start_on_preset = start_on_preset;
+                self.save_settings();
+            }
+            Message::LoadRecipe(id, name) => {
+                let recipe = self
+                    .settings
+                    .recipes
+                    .iter()
+                    .find(|recipe| recipe.name == name);
+                let timer = self.timers.iter_mut().find(|timer| timer.id == id);
+
+                if let (Some(recipe), Some(timer)) = (recipe, timer) {
+                    timer.load_recipe(recipe, &self.settings);
+                }
+            }
+            Message::EditRecipeName(name) => {
+                self.recipe_name_input = name;
+                self.recipe_error = None;
+            }
+            Message::EditRecipeSteps(steps) => {
+                self.recipe_steps_input = steps;
+                self.recipe_error = None;
+            }
+            Message::AddRecipe => {
+                let name = self.recipe_name_input.trim();
+
+                if name.is_empty() {
+                    self.recipe_error = Some("Recipes need a name".to_string());
+                } else if self
+                    .settings
+                    .recipes
+                    .iter()
+                    .any(|recipe| recipe.name == name)
+                {
+                    self.recipe_error = Some(format!("There's already a recipe called {name}"));
+                } else {
+                    match recipe::parse_steps(&self.recipe_steps_input) {
+                        Ok(steps) if steps.iter().any(Duration::is_zero) => {
+                            self.recipe_error = Some("Steps can't be zero".to_string());
+                        }
+                        Ok(steps) => {
+                            // Keep the selected timer's sound, unless it's just the default one.
+                            let alarm = &self.selected_timer().alarm;
+
+                            let mut recipe = Recipe {
+                                name: name.to_string(),
+                                steps,
+                                alarm_path: None,
+                                synth: None,
+                                tone: None,
+                            };
+                            if !alarm.is_default_sound(&self.settings) {
+                                recipe.alarm_path = alarm.path.clone();
+                                recipe.synth = alarm.synth.clone();
+                                recipe.tone = Some(alarm.tone);
                             }
 
-                            old_state.push_str(&new_digit.to_string());
-                            old_state.clone()
-                        }
-                        EditingState::NotEditing => {
-                            // This shouldn't happen, but if it does, then just flip things on.
-                            let s = new_digit.to_string();
-                            self.is_editing = EditingState::Editing(s.clone());
-                            s
-                        }
-                    };
+                            self.settings.recipes.push(recipe);
+                            self.save_settings();
 
-                    self.update_to_wait_from_str(&current);
-                }
-                Message::EditBackspace => {
-                    let current = match &mut self.is_editing {
-                        EditingState::Editing(old_state) => {
-                            old_state.pop();
-                            old_state.clone()
+                            self.recipe_name_input.clear();
+                            self.recipe_steps_input.clear();
+                            self.recipe_error = None;
                         }
-                        EditingState::NotEditing => {
-                            // This shouldn't happen, but if it does, then it would just be the empty string anyway.
-                            // Flip it on and return the empty string.
-                            self.is_editing = EditingState::Editing(String::new());
-                            String::new()
+                        Err(err) => {
+                            self.recipe_error = Some(format!("Couldn't read the steps: {err}"));
                         }
-                    };
-
-                    self.update_to_wait_from_str(&current);
+                    }
                 }
-                Message::EnableTimer => {
-                    self.state = TimerAppState::Started {
-                        start_instant: Instant::now(),
-                        time_left: self.to_wait.clone(),
-                        total_wait: self.to_wait.clone(),
-                        is_paused: IsPaused::NotPaused,
-                    };
-                    self.is_editing = EditingState::NotEditing;
+            }
+            Message::RemoveRecipe(index) => {
+                if index < self.settings.recipes.len() {
+                    self.settings.recipes.remove(index);
+                    self.save_settings();
                 }
-                Message::EnableEditTimer => {
-                    self.is_editing = EditingState::Editing(String::new());
+            }
+            Message::SetVolume(volume) => {
+                self.selected_timer_mut().alarm.set_volume(volume);
+            }
+            Message::SetFadeIn(secs) => {
+                self.selected_timer_mut().alarm.fade_in =
+                    (secs > 0).then(|| Duration::from_secs(secs.into()));
+            }
+            Message::SaveSettings => {
+                // Sliders only save once they're released, rather than on every step.
+                self.save_settings();
+            }
+            Message::Timer(id, message) => {
+                let Some(timer) = self.timers.iter_mut().find(|timer| timer.id == id) else {
+                    return Command::none();
+                };
+
+                let is_start = matches!(
+                    message,
+                    TimerMessage::EnableTimer | TimerMessage::UsePreset { start: true, .. }
+                );
+                timer.update(message);
+
+                // Recipes have their own durations, so they shouldn't change the default.
+                if is_start
+                    && timer.recipe.is_none()
+                    && self.settings.default_duration != timer.core.to_wait
+                {
+                    self.settings.default_duration = timer.core.to_wait;
+                    self.save_settings();
                 }
-                // Message::DisableEditTimer => {
-                //     self.is_editing = EditingState::NotEditing;
-                // }
-                _ => {}
-            },
-            TimerAppState::Started {
-                start_instant,
-                time_left,
-                total_wait,
-                is_paused,
-            } => match message {
-                Message::Tick => {
-                    let new_duration = total_wait.saturating_sub(start_instant.elapsed());
-                    *time_left = new_duration;
-
-                    if new_duration.is_zero() {
-                        self.play_audio().unwrap();
-                        self.state = TimerAppState::Ringing;
+
+                return self.sync_status();
+            }
+            Message::NotifierConnected(notifier) => {
+                self.notifier = notifier;
+                return self.sync_status();
+            }
+            Message::NotificationShown(timer_id, id) => {
+                let Some(id) = id else {
+                    return Command::none();
+                };
+
+                match self.notifications.get_mut(&timer_id) {
+                    Some(notification) => *notification = Some(id),
+                    None => {
+                        // The alarm was dealt with before the notification even showed up.
+                        if let Some(notifier) = &self.notifier {
+                            return close_notification(notifier.clone(), id);
+                        }
                     }
                 }
-                Message::TogglePause => match is_paused {
-                    IsPaused::Paused { pause_start } => {
-                        *start_instant += pause_start.elapsed();
-                        *is_paused = IsPaused::NotPaused;
-                    }
-                    IsPaused::NotPaused => {
-                        *is_paused = IsPaused::Paused {
-                            pause_start: Instant::now(),
-                        }
+            }
+            Message::NotificationClosed => {}
+            Message::NotificationAction(id, action) => {
+                let Some(timer_id) =
+                    self.notifications
+                        .iter()
+                        .find_map(|(timer_id, notification)| {
+                            (*notification == Some(id)).then_some(*timer_id)
+                        })
+                else {
+                    return Command::none();
+                };
+
+                // Picking an action already closes the notification, so there's nothing left to close.
+                self.notifications.remove(&timer_id);
+
+                let message = match action {
+                    NotificationAction::Dismiss => TimerMessage::StopRinging,
+                    NotificationAction::Snooze => TimerMessage::Snooze,
+                };
+                return self.update(Message::Timer(timer_id, message));
+            }
+            #[cfg(unix)]
+            Message::Control(request, responder) => {
+                if let Some(args) = request.launch_args() {
+                    // Another launch's timer shouldn't replace one that's in use.
+                    if args.sets_up_timer() && !self.selected_timer().core.is_stopped() {
+                        self.selected = self.add_timer();
                     }
-                },
-                _ => {}
-            },
-            TimerAppState::Ringing => match message {
-                Message::StopRinging => {
-                    self.stop_audio();
+                    self.apply_args(self.selected, &args);
+                    responder.respond(ipc::Response::ok(self.control_status()));
+
+                    return Command::batch(vec![
+                        self.sync_status(),
+                        window::gain_focus(window::Id::MAIN),
+                    ]);
                 }
-                _ => {}
-            },
+
+                let message = match request.timer_message(&self.selected_timer().core) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        responder.respond(ipc::Response::error(err));
+                        return Command::none();
+                    }
+                };
+
+                let command = match message {
+                    Some(message) => self.update(Message::Timer(self.selected, message)),
+                    None => Command::none(),
+                };
+                responder.respond(ipc::Response::ok(self.control_status()));
+
+                return command;
+            }
+            #[cfg(feature = "tray")]
+            Message::Tray(action) => {
+                let message = match action {
+                    TrayAction::ToggleWindow => {
+                        let Some(tray) = &mut self.tray else {
+                            return Command::none();
+                        };
+
+                        // Subscriptions keep running while the window is hidden, so timers carry on ticking.
+                        let is_window_hidden = !tray.is_window_hidden();
+                        tray.set_status(TrayStatus {
+                            is_window_hidden,
+                            ..tray.status().clone()
+                        });
+
+                        return if is_window_hidden {
+                            window::change_mode(window::Id::MAIN, window::Mode::Hidden)
+                        } else {
+                            Command::batch(vec![
+                                window::change_mode(window::Id::MAIN, window::Mode::Windowed),
+                                window::gain_focus(window::Id::MAIN),
+                            ])
+                        };
+                    }
+                    TrayAction::Start => TimerMessage::EnableTimer,
+                    TrayAction::TogglePause => TimerMessage::TogglePause,
+                    TrayAction::Reset => TimerMessage::ResetTimer,
+                    TrayAction::StopRinging => TimerMessage::StopRinging,
+                    TrayAction::Quit => return self.update(Message::CloseRequested),
+                };
+
+                return self.update(Message::Timer(self.selected, message));
+            }
         }
 
         Command::none()
     }
 
-    fn view(&self) -> Element<Self::Message> {
+    fn view(&self) -> Element<'_, Self::Message> {
+        if self.show_settings {
+            return self.settings_view();
+        }
+
         let mut content = column![]
             .align_items(Alignment::Center)
             .spacing(20)
             .max_width(600);
 
+        let timer = self.selected_timer();
+        let id = timer.id;
+
+        let mut tabs = row![].spacing(10).align_items(Alignment::Center);
+        for (index, other) in self.timers.iter().enumerate() {
+            let name = self.timer_name(index);
+            let tab_text = if other.core.is_ringing() {
+                format!("{name} (ringing)")
+            } else {
+                format!("{name} {}", clock_duration(other.core.displayed_duration()))
+            };
+
+            tabs = tabs.push(
+                button(textt(tab_text).size(BUTTON_FONT_SIZE))
+                    .padding(6)
+                    .style(if other.id == id {
+                        theme::Button::Primary
+                    } else {
+                        theme::Button::Secondary
+                    })
+                    .on_press(Message::SelectTimer(other.id)),
+            );
+        }
+        tabs = tabs.push(
+            button(textt("+").size(BUTTON_FONT_SIZE))
+                .padding(6)
+                .style(theme::Button::Secondary)
+                .on_press(Message::AddTimer),
+        );
+
+        let tabs = scrollable(tabs).direction(scrollable::Direction::Horizontal(
+            scrollable::Properties::default(),
+        ));
+
+        let label_input = text_input("Label", &timer.label)
+            .on_input(move |label| Message::Timer(id, TimerMessage::EditLabel(label)))
+            .size(BUTTON_FONT_SIZE)
+            .width(200);
+
+        let remove_button = button(textt("Remove").size(BUTTON_FONT_SIZE))
+            .padding(6)
+            .style(theme::Button::Destructive)
+            .on_press_maybe((self.timers.len() > 1).then_some(Message::RemoveTimer(id)));
+
+        let settings_button = button(textt("Settings").size(BUTTON_FONT_SIZE))
+            .padding(6)
+            .style(theme::Button::Secondary)
+            .on_press(Message::ToggleSettings);
+
+        content = content.push(tabs).push(
+            row![label_input, remove_button, settings_button]
+                .spacing(10)
+                .align_items(Alignment::Center),
+        );
+
+        if let Some(err) = &timer.audio_error {
+            content = content.push(audio_error_banner(err));
+        }
+
+        if let TimerAppState::Stopped = timer.core.state {
+            let mode_button = |mode: TimerMode, name: &'static str| {
+                button(
+                    textt(name)
+                        .size(BUTTON_FONT_SIZE)
+                        .horizontal_alignment(Horizontal::Center),
+                )
+                .width(90)
+                .padding(6)
+                .style(if timer.core.mode == mode {
+                    theme::Button::Primary
+                } else {
+                    theme::Button::Secondary
+                })
+                .on_press(Message::Timer(id, TimerMessage::SetMode(mode)))
+            };
+
+            content = content.push(
+                row![
+                    mode_button(TimerMode::Countdown, "Timer"),
+                    mode_button(TimerMode::Stopwatch, "Stopwatch"),
+                    mode_button(TimerMode::Sequence, "Pomodoro"),
+                ]
+                .spacing(10),
+            );
+
+            if !self.settings.recipes.is_empty() {
+                let names: Vec<String> = self
+                    .settings
+                    .recipes
+                    .iter()
+                    .map(|recipe| recipe.name.clone())
+                    .collect();
+
+                content = content.push(
+                    pick_list(names, timer.recipe.clone(), move |name| {
+                        Message::LoadRecipe(id, name)
+                    })
+                    .placeholder("Load a recipe...")
+                    .text_size(BUTTON_FONT_SIZE),
+                );
+            }
+        }
+
+        if let Some(recipe) = &timer.recipe {
+            content = content.push(textt(recipe).size(UNIT_FONT_SIZE).font(SEMIBOLD_FONT));
+        }
+
+        if let Some(step) = timer.core.describe_step() {
+            content = content.push(textt(step).size(BUTTON_FONT_SIZE));
+        }
+
+        if timer.core.mode == TimerMode::Sequence {
+            content = content.push(
+                textt(timer.core.sequence.describe())
+                    .size(UNIT_FONT_SIZE)
+                    .font(SEMIBOLD_FONT),
+            );
+        }
+
+        if let Some(snoozed) = describe_snoozes(timer.core.snoozes) {
+            content = content.push(textt(snoozed).size(BUTTON_FONT_SIZE));
+        }
+
         let mut is_editing = false;
 
-        let (left_button, right_button) = match &self.state {
+        let (left_button, right_button) = match &timer.core.state {
             TimerAppState::Stopped => {
-                if let EditingState::Editing(s) = &self.is_editing {
+                if let EditingState::Editing(s) = &timer.is_editing {
                     is_editing = true;
 
-                    // Assuming the "string" is something like hh(...)mmss, where hh can be any number of digits:
-                    let (hours, minutes, seconds) = string_to_hms(&s);
+                    // Assuming the "string" is something like hh(...)mmss, where hh can be any number of digits.
+                    // This only ever contains digits, so it shouldn't fail to parse.
+                    let (hours, minutes, seconds) =
+                        duration_parser::parse_digit_entry(s).unwrap_or_default();
 
-                    let (curr_hours, curr_minutes, curr_seconds) = human_duration(self.to_wait);
+                    let (curr_hours, curr_minutes, curr_seconds) =
+                        human_duration(timer.core.to_wait);
                     let mut wrapper = row!().spacing(10);
 
                     let h_val = match hours {
@@ -449,27 +1759,21 @@ impl Application for TimerApp {
                     // TODO: Ideally wrap this in a container with just one border on the bottom - but you can't
                     // do that in iced right now!
                     content = content.push(wrapper);
+                } else if timer.core.mode == TimerMode::Stopwatch {
+                    content =
+                        content.push(duration_display(parse_stopwatch_duration(Duration::ZERO)));
+                } else if timer.core.mode == TimerMode::Sequence {
+                    content = content.push(duration_display(parse_duration(
+                        timer.core.displayed_duration(),
+                    )));
                 } else {
-                    let durations = parse_duration(self.to_wait);
-                    let mut displayed_duration = row!().spacing(10);
-                    for (amount, unit) in durations {
-                        displayed_duration = displayed_duration.push(
-                            row!(
-                                textt(amount).size(TIME_FONT_SIZE).font(SEMIBOLD_FONT),
-                                textt(unit)
-                                    .size(UNIT_FONT_SIZE)
-                                    .font(SEMIBOLD_FONT)
-                                    .line_height(LineHeight::Absolute(TIME_FONT_SIZE.into()))
-                            )
-                            .align_items(Alignment::End),
-                        );
-                    }
+                    let displayed_duration = duration_display(parse_duration(timer.core.to_wait));
 
                     // This is so jankkkkkk.
                     let edit_button_wrapper = button(displayed_duration)
                         .style(theme::Button::custom(styling::button::Transparent))
                         .padding(0)
-                        .on_press(Message::EnableEditTimer);
+                        .on_press(Message::Timer(id, TimerMessage::EnableEditTimer));
 
                     content = content.push(edit_button_wrapper);
                 }
@@ -481,7 +1785,7 @@ impl Application for TimerApp {
                 )
                 .width(90)
                 .padding(10)
-                .on_press(Message::EnableTimer);
+                .on_press(Message::Timer(id, TimerMessage::EnableTimer));
 
                 let right_button = button(
                     textt("Reset")
@@ -495,37 +1799,29 @@ impl Application for TimerApp {
             }
             TimerAppState::Started {
                 time_left,
+                elapsed,
                 is_paused,
                 ..
             } => {
-                let durations = parse_duration(*time_left);
-                let mut displayed_duration = row!().spacing(10);
-                for (amount, unit) in durations {
-                    displayed_duration = displayed_duration.push(
-                        row!(
-                            textt(amount).size(TIME_FONT_SIZE).font(SEMIBOLD_FONT),
-                            textt(unit)
-                                .size(UNIT_FONT_SIZE)
-                                .font(SEMIBOLD_FONT)
-                                .line_height(LineHeight::Absolute(TIME_FONT_SIZE.into()))
-                        )
-                        .align_items(Alignment::End),
-                    );
-                }
+                let durations = match timer.core.mode {
+                    TimerMode::Countdown | TimerMode::Sequence => parse_duration(*time_left),
+                    TimerMode::Stopwatch => parse_stopwatch_duration(*elapsed),
+                };
+                let displayed_duration = duration_display(durations);
 
                 content = content.push(displayed_duration);
 
                 let left_button = button(
                     textt(match is_paused {
                         IsPaused::Paused { .. } => "Resume",
-                        IsPaused::NotPaused { .. } => "Pause",
+                        IsPaused::NotPaused => "Pause",
                     })
                     .size(BUTTON_FONT_SIZE)
                     .horizontal_alignment(Horizontal::Center),
                 )
                 .width(90)
                 .padding(10)
-                .on_press(Message::TogglePause);
+                .on_press(Message::Timer(id, TimerMessage::TogglePause));
 
                 let right_button = button(
                     textt("Reset")
@@ -534,32 +1830,49 @@ impl Application for TimerApp {
                 )
                 .width(90)
                 .padding(10)
-                .on_press(Message::ResetTimer);
+                .on_press(Message::Timer(id, TimerMessage::ResetTimer));
 
                 (left_button, right_button)
             }
-            TimerAppState::Ringing => {
-                content = content.push(
-                    row!(row!(
-                        textt("0").size(TIME_FONT_SIZE).font(SEMIBOLD_FONT),
-                        textt("s")
-                            .size(UNIT_FONT_SIZE)
-                            .font(SEMIBOLD_FONT)
-                            .line_height(LineHeight::Absolute(TIME_FONT_SIZE.into()))
-                    )
-                    .align_items(Alignment::End))
-                    .spacing(10)
-                    .align_items(Alignment::End),
-                );
+            TimerAppState::Ringing { .. } => {
+                // If the alarm can't be heard, flash the display instead.
+                let ringing_style = theme::Text::Color(if timer.visual_alarm && self.flash_on {
+                    ERROR_TEXT_COLOR
+                } else {
+                    DEFAULT_TEXT_COLOR
+                });
+
+                let mut overtime = row!().spacing(10).align_items(Alignment::End);
+                for (amount, unit) in parse_overtime(timer.core.overtime().unwrap_or_default()) {
+                    overtime = overtime.push(
+                        row!(
+                            textt(amount)
+                                .size(TIME_FONT_SIZE)
+                                .font(SEMIBOLD_FONT)
+                                .style(ringing_style),
+                            textt(unit)
+                                .size(UNIT_FONT_SIZE)
+                                .font(SEMIBOLD_FONT)
+                                .line_height(LineHeight::Absolute(TIME_FONT_SIZE.into()))
+                                .style(ringing_style)
+                        )
+                        .align_items(Alignment::End),
+                    );
+                }
+                content = content.push(overtime);
 
                 let left_button = button(
-                    textt("Okay")
-                        .size(BUTTON_FONT_SIZE)
-                        .horizontal_alignment(Horizontal::Center),
+                    textt(if timer.core.mode == TimerMode::Sequence {
+                        "Next"
+                    } else {
+                        "Okay"
+                    })
+                    .size(BUTTON_FONT_SIZE)
+                    .horizontal_alignment(Horizontal::Center),
                 )
                 .width(90)
                 .padding(10)
-                .on_press(Message::StopRinging);
+                .on_press(Message::Timer(id, TimerMessage::StopRinging));
 
                 let right_button = button(
                     textt("Reset")
@@ -568,69 +1881,304 @@ impl Application for TimerApp {
                 )
                 .width(90)
                 .padding(10)
-                .on_press(Message::ResetTimer);
+                .on_press(Message::Timer(id, TimerMessage::ResetTimer));
 
                 (left_button, right_button)
             }
         };
 
-        let buttons = row!(
-            left_button.style(theme::Button::Primary),
-            right_button.style(theme::Button::Secondary),
-        )
-        .spacing(40);
+        if timer.core.is_stopped()
+            && timer.core.mode == TimerMode::Countdown
+            && !self.settings.presets.is_empty()
+        {
+            let mut presets = row![].spacing(10);
+            for &duration in &self.settings.presets {
+                presets = presets.push(
+                    button(textt(compact_duration(duration)).size(BUTTON_FONT_SIZE))
+                        .padding(6)
+                        .style(if duration == timer.core.to_wait {
+                            theme::Button::Primary
+                        } else {
+                            theme::Button::Secondary
+                        })
+                        .on_press(Message::Timer(
+                            id,
+                            TimerMessage::UsePreset {
+                                duration,
+                                start: self.settings.start_on_preset,
+                            },
+                        )),
+                );
+            }
+
+            content = content.push(scrollable(presets).direction(
+                scrollable::Direction::Horizontal(scrollable::Properties::default()),
+            ));
+        }
+
+        let mut buttons = row!(left_button.style(theme::Button::Primary)).spacing(40);
 
+        let middle_button = match timer.core.state {
+            TimerAppState::Started { .. } => Some(("Lap", TimerMessage::Lap)),
+            TimerAppState::Ringing { .. } => Some(("Snooze", TimerMessage::Snooze)),
+            TimerAppState::Stopped => None,
+        };
+
+        if let Some((name, message)) = middle_button {
+            buttons = buttons.push(
+                button(
+                    textt(name)
+                        .size(BUTTON_FONT_SIZE)
+                        .horizontal_alignment(Horizontal::Center),
+                )
+                .width(90)
+                .padding(10)
+                .style(theme::Button::Secondary)
+                .on_press(Message::Timer(id, message)),
+            );
+        }
+
+        buttons = buttons.push(right_button.style(theme::Button::Secondary));
         content = content.push(buttons);
 
+        if timer.core.is_started() && timer.core.mode != TimerMode::Stopwatch {
+            let mut adjust_buttons = row![].spacing(10);
+            for (name, secs) in TIME_ADJUSTMENTS {
+                adjust_buttons = adjust_buttons.push(
+                    button(textt(name).size(BUTTON_FONT_SIZE))
+                        .padding(6)
+                        .style(theme::Button::Secondary)
+                        .on_press(Message::Timer(id, TimerMessage::AdjustTime(secs))),
+                );
+            }
+            content = content.push(adjust_buttons);
+        }
+
+        if !timer.core.laps.is_empty() {
+            let mut laps = column![].spacing(4);
+
+            // Show the newest lap first, like most stopwatches do.
+            for (index, lap) in timer.core.laps.iter().enumerate().rev() {
+                laps = laps.push(
+                    row![
+                        textt(format!("Lap {}", index + 1))
+                            .size(BUTTON_FONT_SIZE)
+                            .width(Length::Fill),
+                        textt(format!("+{}", precise_clock_duration(lap.split)))
+                            .size(BUTTON_FONT_SIZE)
+                            .width(Length::Fill)
+                            .horizontal_alignment(Horizontal::Right),
+                        textt(precise_clock_duration(lap.total))
+                            .size(BUTTON_FONT_SIZE)
+                            .width(Length::Fill)
+                            .horizontal_alignment(Horizontal::Right),
+                    ]
+                    .spacing(10),
+                );
+            }
+
+            content = content.push(scrollable(laps).height(150).width(300)).push(
+                button(textt("Copy as CSV").size(BUTTON_FONT_SIZE))
+                    .padding(6)
+                    .style(theme::Button::Secondary)
+                    .on_press(Message::CopyLaps(id)),
+            );
+        }
+
         NumInputContainer::new(
             container(content)
                 .width(Length::Fill)
                 .height(Length::Fill)
                 .center_x()
                 .center_y(),
-            Box::new(Message::EditNewNum),
-            Box::new(|| Message::EditBackspace),
+            Box::new(move |digit| Message::Timer(id, TimerMessage::EditNewNum(digit))),
+            Box::new(move || Message::Timer(id, TimerMessage::EditBackspace)),
             !is_editing,
         )
         .into()
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
-        match &self.state {
-            TimerAppState::Started { is_paused, .. } => match is_paused {
-                IsPaused::Paused { .. } => Subscription::none(),
-                IsPaused::NotPaused => {
-                    iced::time::every(Duration::from_millis(100)).map(|_| Message::Tick)
+        let mut subscriptions = vec![];
+
+        // Ringing timers keep ticking too, so they know when to stop (or start again).
+        if self
+            .timers
+            .iter()
+            .any(|timer| timer.core.is_running() || timer.core.is_ringing())
+        {
+            subscriptions
+                .push(iced::time::every(Duration::from_millis(100)).map(|_| Message::Tick));
+        }
+
+        if self.timers.iter().any(|timer| timer.visual_alarm) {
+            subscriptions
+                .push(iced::time::every(Duration::from_millis(500)).map(|_| Message::Flash));
+        }
+
+        for timer in &self.timers {
+            if timer.core.is_ringing() {
+                if let (TimerMode::Sequence, Some(delay)) =
+                    (timer.core.mode, timer.core.sequence.config.auto_advance)
+                {
+                    // Acknowledging the alarm is what moves a sequence on to its next phase.
+                    subscriptions.push(
+                        iced::time::every(delay)
+                            .with(timer.id)
+                            .map(|(id, _)| Message::Timer(id, TimerMessage::StopRinging)),
+                    );
                 }
-            },
-            TimerAppState::Stopped => match self.is_editing {
-                EditingState::Editing(_) => keyboard::on_key_press(|key, _modifier| match key {
-                    keyboard::Key::Named(keyboard::key::Named::Enter) => Some(Message::EnableTimer),
+            }
+        }
+
+        let timer = self.selected_timer();
+        if timer.core.is_started() && timer.core.mode != TimerMode::Stopwatch {
+            subscriptions.push(
+                keyboard::on_key_press(|key, modifiers| {
+                    let secs = match key.as_ref() {
+                        keyboard::Key::Named(keyboard::key::Named::ArrowUp)
+                            if modifiers.shift() =>
+                        {
+                            5 * 60
+                        }
+                        keyboard::Key::Named(keyboard::key::Named::ArrowUp)
+                        | keyboard::Key::Character("+" | "=") => 60,
+                        keyboard::Key::Named(keyboard::key::Named::ArrowDown)
+                        | keyboard::Key::Character("-") => -60,
+                        _ => return None,
+                    };
+
+                    Some(TimerMessage::AdjustTime(secs))
+                })
+                .with(timer.id)
+                .map(|(id, message)| Message::Timer(id, message)),
+            );
+        }
+
+        if timer.core.is_ringing() {
+            subscriptions.push(
+                keyboard::on_key_press(|key, _modifier| match key.as_ref() {
+                    keyboard::Key::Character("s") => Some(TimerMessage::Snooze),
                     _ => None,
-                }),
-                EditingState::NotEditing => Subscription::none(),
-            },
-            TimerAppState::Ringing => {
-                // This is a bit silly but this is a fast way to not have to import more crates on my end so...
+                })
+                .with(timer.id)
+                .map(|(id, message)| Message::Timer(id, message)),
+            );
+        }
 
-                iced::time::every(Duration::from_secs(60)).map(|_| Message::StopRinging)
-            }
+        if let (TimerAppState::Stopped, EditingState::Editing(_)) =
+            (&timer.core.state, &timer.is_editing)
+        {
+            subscriptions.push(
+                keyboard::on_key_press(|key, _modifier| match key {
+                    keyboard::Key::Named(keyboard::key::Named::Enter) => {
+                        Some(TimerMessage::EnableTimer)
+                    }
+                    _ => None,
+                })
+                .with(timer.id)
+                .map(|(id, message)| Message::Timer(id, message)),
+            );
+        }
+
+        if let Some(notifier) = &self.notifier {
+            subscriptions.push(
+                notifications::actions(notifier.clone())
+                    .map(|(id, action)| Message::NotificationAction(id, action)),
+            );
+        }
+
+        #[cfg(unix)]
+        if self.is_primary {
+            subscriptions.push(
+                ipc::listen().map(|(request, responder)| Message::Control(request, responder)),
+            );
         }
+
+        #[cfg(feature = "tray")]
+        if self.tray.is_some() {
+            subscriptions.push(tray::actions().map(Message::Tray));
+        }
+
+        let window_subscription = event::listen_with(|event, _status| match event {
+            Event::Window(_, window::Event::Moved { x, y }) => Some(Message::WindowMoved { x, y }),
+            Event::Window(_, window::Event::Resized { width, height }) => {
+                Some(Message::WindowResized { width, height })
+            }
+            Event::Window(_, window::Event::CloseRequested) => Some(Message::CloseRequested),
+            _ => None,
+        });
+
+        subscriptions.push(window_subscription);
+
+        Subscription::batch(subscriptions)
     }
 }
 
-fn main() -> iced::Result {
-    TimerApp::run(Settings {
+fn main() -> eyre::Result<()> {
+    let args = Args::parse();
+
+    if let Some(cli::Command::Ctl { command, json }) = args.command {
+        #[cfg(unix)]
+        return ipc::run_ctl(command, json);
+
+        #[cfg(not(unix))]
+        {
+            let _ = (command, json);
+            eyre::bail!("`timerys ctl` is only supported on Unix-like systems");
+        }
+    }
+
+    let settings = Settings::load()?;
+
+    if args.headless {
+        return headless::run(args, settings);
+    }
+
+    // Hold on to this until the window closes, so later launches know it's open.
+    #[cfg(unix)]
+    let instance_lock = match instance::lock() {
+        Ok(Some(lock)) => Some(lock),
+        Ok(None) if args.new_instance => None,
+        Ok(None) => return instance::forward(&args),
+        Err(err) => {
+            eprintln!("Failed to check for a running instance: {err:?}");
+            None
+        }
+    };
+    #[cfg(unix)]
+    let is_primary = instance_lock.is_some();
+
+    let position = match (settings.window.x, settings.window.y) {
+        (Some(x), Some(y)) => window::Position::Specific(Point::new(x as f32, y as f32)),
+        _ => window::Position::default(),
+    };
+
+    TimerApp::run(iced::Settings {
         antialiasing: true,
         window: window::Settings {
-            size: Size::new(400.0, 600.0),
-            min_size: Some(Size::new(400.0, 600.0)),
-            max_size: Some(Size::new(400.0, 600.0)),
+            size: Size::new(settings.window.width, settings.window.height),
+            position,
+            min_size: Some(Size::new(
+                settings::DEFAULT_WINDOW_WIDTH,
+                settings::DEFAULT_WINDOW_HEIGHT,
+            )),
             resizable: true,
             decorations: true,
+            exit_on_close_request: false,
             ..Default::default()
         },
         default_font: DEFAULT_FONT,
-        ..Default::default()
-    })
+        ..iced::Settings::with_flags(AppFlags {
+            settings,
+            args,
+            #[cfg(unix)]
+            is_primary,
+        })
+    })?;
+
+    #[cfg(unix)]
+    drop(instance_lock);
+
+    Ok(())
 }
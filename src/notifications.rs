@@ -0,0 +1,370 @@
+//! Desktop notifications for when a timer starts ringing, so it's noticed even if the window is buried. On Linux,
+//! these go through the freedesktop notification service over D-Bus; other platforms don't have any yet.
+
+use iced::{futures::stream, Subscription};
+
+/// What was clicked on a notification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NotificationAction {
+    Dismiss,
+    Snooze,
+}
+
+impl NotificationAction {
+    /// The action keys and labels, in the flattened form the notification spec wants them in.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    const ACTIONS: [&'static str; 4] = ["dismiss", "Dismiss", "snooze", "Snooze"];
+
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "dismiss" => Some(NotificationAction::Dismiss),
+            "snooze" => Some(NotificationAction::Snooze),
+            _ => None,
+        }
+    }
+}
+
+/// The ID the notification service gave a notification.
+pub(crate) type NotificationId = u32;
+
+/// Listens for actions on any notification we've shown.
+pub(crate) fn actions(notifier: Notifier) -> Subscription<(NotificationId, NotificationAction)> {
+    use iced::futures::StreamExt;
+
+    struct Actions;
+
+    let actions = stream::once(async move {
+        match notifier.action_stream().await {
+            Ok(actions) => actions.boxed(),
+            Err(err) => {
+                eprintln!("Failed to listen for notification actions: {err:?}");
+                stream::empty().boxed()
+            }
+        }
+    })
+    .flatten();
+
+    iced::subscription::run_with_id(std::any::TypeId::of::<Actions>(), actions)
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) use dbus::Notifier;
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) use unsupported::Notifier;
+
+#[cfg(target_os = "linux")]
+mod dbus {
+    use std::collections::HashMap;
+
+    use eyre::WrapErr;
+    use iced::futures::{Stream, StreamExt};
+    use zbus::{proxy::CacheProperties, zvariant::Value, Connection};
+
+    use super::{NotificationAction, NotificationId};
+
+    /// Don't expire the notification on a timer; it's closed once the alarm is dealt with.
+    const NEVER_EXPIRE: i32 = 0;
+
+    /// The "critical" urgency, which most services keep on screen until it's clicked.
+    const CRITICAL_URGENCY: u8 = 2;
+
+    #[zbus::proxy(
+        interface = "org.freedesktop.Notifications",
+        default_service = "org.freedesktop.Notifications",
+        default_path = "/org/freedesktop/Notifications",
+        gen_blocking = false
+    )]
+    trait Notifications {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            app_name: &str,
+            replaces_id: u32,
+            app_icon: &str,
+            summary: &str,
+            body: &str,
+            actions: &[&str],
+            hints: HashMap<&str, Value<'_>>,
+            expire_timeout: i32,
+        ) -> zbus::Result<u32>;
+
+        fn close_notification(&self, id: u32) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+    }
+
+    /// A connection to the notification service.
+    #[derive(Clone, Debug)]
+    pub(crate) struct Notifier {
+        connection: Connection,
+    }
+
+    impl Notifier {
+        /// Connects to the notification service on the session bus.
+        pub(crate) async fn connect() -> eyre::Result<Self> {
+            let connection = Connection::session()
+                .await
+                .wrap_err("failed to connect to the session bus")?;
+
+            Ok(Self::with_connection(connection))
+        }
+
+        pub(crate) fn with_connection(connection: Connection) -> Self {
+            Self { connection }
+        }
+
+        async fn proxy(&self) -> zbus::Result<NotificationsProxy<'static>> {
+            NotificationsProxy::builder(&self.connection)
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await
+        }
+
+        /// Shows a notification with "Dismiss" and "Snooze" buttons. This takes `self` by value so the future can
+        /// be handed off to the runtime.
+        pub(crate) async fn show(
+            self,
+            summary: String,
+            body: String,
+        ) -> eyre::Result<NotificationId> {
+            let hints = HashMap::from([("urgency", Value::from(CRITICAL_URGENCY))]);
+
+            self.proxy()
+                .await?
+                .notify(
+                    "Timerys",
+                    0,
+                    "",
+                    &summary,
+                    &body,
+                    &NotificationAction::ACTIONS,
+                    hints,
+                    NEVER_EXPIRE,
+                )
+                .await
+                .wrap_err("failed to show the notification")
+        }
+
+        /// Closes a notification, e.g. if the alarm was dealt with from the window instead.
+        pub(crate) async fn close(self, id: NotificationId) -> eyre::Result<()> {
+            self.proxy()
+                .await?
+                .close_notification(id)
+                .await
+                .wrap_err("failed to close the notification")
+        }
+
+        /// Every action the user picks on one of our notifications. Actions from other apps' notifications, and ones
+        /// we don't know about, are skipped.
+        pub(crate) async fn action_stream(
+            self,
+        ) -> eyre::Result<impl Stream<Item = (NotificationId, NotificationAction)>> {
+            let signals = self
+                .proxy()
+                .await?
+                .receive_action_invoked()
+                .await
+                .wrap_err("failed to subscribe to notification actions")?;
+
+            Ok(signals.filter_map(|signal| async move {
+                let args = signal.args().ok()?;
+                let action = NotificationAction::from_key(&args.action_key)?;
+
+                Some((args.id, action))
+            }))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::{
+            io::{BufRead, BufReader},
+            process::{Child, Command, Stdio},
+            sync::{Arc, Mutex},
+        };
+
+        use zbus::{connection, object_server::SignalContext, zvariant::OwnedValue};
+
+        use super::*;
+
+        const PATH: &str = "/org/freedesktop/Notifications";
+
+        /// A private session bus, so the tests don't pop up real notifications (or clash with a real service).
+        struct TestBus {
+            daemon: Child,
+            address: String,
+        }
+
+        impl TestBus {
+            /// Starts a bus, or returns `None` if `dbus-daemon` isn't installed.
+            fn start() -> Option<Self> {
+                let mut daemon = Command::new("dbus-daemon")
+                    .args(["--session", "--nofork", "--print-address=1"])
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .ok()?;
+
+                let mut address = String::new();
+                BufReader::new(daemon.stdout.take()?)
+                    .read_line(&mut address)
+                    .ok()?;
+
+                Some(Self {
+                    daemon,
+                    address: address.trim().to_string(),
+                })
+            }
+
+            async fn connect(&self) -> Connection {
+                connection::Builder::address(self.address.as_str())
+                    .unwrap()
+                    .build()
+                    .await
+                    .unwrap()
+            }
+        }
+
+        impl Drop for TestBus {
+            fn drop(&mut self) {
+                let _ = self.daemon.kill();
+                let _ = self.daemon.wait();
+            }
+        }
+
+        /// The summary, body, and actions of a notification.
+        type Shown = (String, String, Vec<String>);
+
+        /// Stands in for the notification service, and records what it's asked to do.
+        #[derive(Clone, Default)]
+        struct MockDaemon {
+            shown: Arc<Mutex<Vec<Shown>>>,
+            closed: Arc<Mutex<Vec<u32>>>,
+        }
+
+        #[zbus::interface(name = "org.freedesktop.Notifications")]
+        impl MockDaemon {
+            #[allow(clippy::too_many_arguments)]
+            fn notify(
+                &self,
+                _app_name: String,
+                _replaces_id: u32,
+                _app_icon: String,
+                summary: String,
+                body: String,
+                actions: Vec<String>,
+                _hints: HashMap<String, OwnedValue>,
+                _expire_timeout: i32,
+            ) -> u32 {
+                let mut shown = self.shown.lock().unwrap();
+                shown.push((summary, body, actions));
+                shown.len() as u32
+            }
+
+            fn close_notification(&self, id: u32) {
+                self.closed.lock().unwrap().push(id);
+            }
+
+            #[zbus(signal)]
+            async fn action_invoked(
+                ctxt: &SignalContext<'_>,
+                id: u32,
+                action_key: &str,
+            ) -> zbus::Result<()>;
+        }
+
+        #[tokio::test]
+        async fn notification_actions_round_trip() {
+            let Some(bus) = TestBus::start() else {
+                eprintln!("Skipping, as dbus-daemon isn't available");
+                return;
+            };
+
+            let daemon = MockDaemon::default();
+            let service = connection::Builder::address(bus.address.as_str())
+                .unwrap()
+                .name("org.freedesktop.Notifications")
+                .unwrap()
+                .serve_at(PATH, daemon.clone())
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+
+            let notifier = Notifier::with_connection(bus.connect().await);
+            let mut actions = Box::pin(notifier.clone().action_stream().await.unwrap());
+
+            let id = notifier
+                .clone()
+                .show("Tea".to_string(), "Time's up!".to_string())
+                .await
+                .unwrap();
+
+            assert_eq!(
+                *daemon.shown.lock().unwrap(),
+                vec![(
+                    "Tea".to_string(),
+                    "Time's up!".to_string(),
+                    NotificationAction::ACTIONS.map(String::from).to_vec()
+                )]
+            );
+
+            let ctxt = SignalContext::new(&service, PATH).unwrap();
+            MockDaemon::action_invoked(&ctxt, id, "something-else")
+                .await
+                .unwrap();
+            MockDaemon::action_invoked(&ctxt, id, "snooze")
+                .await
+                .unwrap();
+            MockDaemon::action_invoked(&ctxt, id, "dismiss")
+                .await
+                .unwrap();
+
+            assert_eq!(actions.next().await, Some((id, NotificationAction::Snooze)));
+            assert_eq!(
+                actions.next().await,
+                Some((id, NotificationAction::Dismiss))
+            );
+
+            notifier.close(id).await.unwrap();
+            assert_eq!(*daemon.closed.lock().unwrap(), vec![id]);
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod unsupported {
+    use iced::futures::{stream, Stream};
+
+    use super::{NotificationAction, NotificationId};
+
+    /// There's no notification service to connect to on this platform, so this can't be created.
+    #[derive(Clone, Debug)]
+    pub(crate) enum Notifier {}
+
+    impl Notifier {
+        pub(crate) async fn show(
+            self,
+            _summary: String,
+            _body: String,
+        ) -> eyre::Result<NotificationId> {
+            match self {}
+        }
+
+        pub(crate) async fn close(self, _id: NotificationId) -> eyre::Result<()> {
+            match self {}
+        }
+
+        pub(crate) async fn action_stream(
+            self,
+        ) -> eyre::Result<impl Stream<Item = (NotificationId, NotificationAction)>> {
+            match self {}
+
+            #[allow(unreachable_code)]
+            Ok(stream::empty())
+        }
+    }
+}
@@ -97,36 +97,33 @@ where
         viewport: &Rectangle,
     ) -> event::Status {
         if !self.ignore_events {
-            match event {
-                Event::Keyboard(ref event) => match event {
-                    keyboard::Event::KeyPressed { key, text, .. } => {
-                        match key {
-                            keyboard::Key::Named(Named::Backspace) => {
-                                shell.publish((self.on_backspace)());
-                                return event::Status::Captured;
-                            }
-                            keyboard::Key::Character(c) => {
-                                if let Ok(digit) = c.parse::<u32>() {
-                                    shell.publish((self.on_num)(digit));
-                                    return event::Status::Captured;
-                                }
-                            }
-                            _ => {}
-                        }
-
-                        // Not sure if this works.
-                        if let Some(text) = text {
-                            if let Some(c) = text.chars().next().filter(|c| !c.is_control()) {
-                                if let Some(num) = c.to_digit(10) {
-                                    shell.publish((self.on_num)(num));
-                                    return event::Status::Captured;
-                                }
-                            }
+            if let Event::Keyboard(keyboard::Event::KeyPressed {
+                ref key, ref text, ..
+            }) = event
+            {
+                match key {
+                    keyboard::Key::Named(Named::Backspace) => {
+                        shell.publish((self.on_backspace)());
+                        return event::Status::Captured;
+                    }
+                    keyboard::Key::Character(c) => {
+                        if let Ok(digit) = c.parse::<u32>() {
+                            shell.publish((self.on_num)(digit));
+                            return event::Status::Captured;
                         }
                     }
                     _ => {}
-                },
-                _ => {}
+                }
+
+                // Not sure if this works.
+                if let Some(text) = text {
+                    if let Some(c) = text.chars().next().filter(|c| !c.is_control()) {
+                        if let Some(num) = c.to_digit(10) {
+                            shell.publish((self.on_num)(num));
+                            return event::Status::Captured;
+                        }
+                    }
+                }
             }
         }
 
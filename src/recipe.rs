@@ -0,0 +1,69 @@
+//! Saved timers ("recipes"), which can be loaded by name rather than set up by hand each time.
+
+use std::{path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::Tone,
+    duration_parser::{self, ParseDurationError},
+    settings::duration_secs_list,
+    synth::SynthConfig,
+};
+
+/// A saved timer, e.g. "Tea: 3m" or "Sourdough: 30m, 45m, 20m". Loaded from the settings file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Recipe {
+    pub(crate) name: String,
+
+    /// What to count down. If there's more than one, they're counted down one after another.
+    #[serde(rename = "steps_secs", with = "duration_secs_list")]
+    pub(crate) steps: Vec<Duration>,
+
+    /// A custom alarm sound for this recipe.
+    #[serde(default)]
+    pub(crate) alarm_path: Option<PathBuf>,
+
+    /// A generated alarm for this recipe. Ignored if there's a custom alarm sound.
+    #[serde(default)]
+    pub(crate) synth: Option<SynthConfig>,
+
+    /// A bundled tone for this recipe. Ignored if there's a custom alarm sound or a generated one.
+    #[serde(default)]
+    pub(crate) tone: Option<Tone>,
+}
+
+/// Parses a comma-separated list of durations, e.g. `30m, 45m, 20m`.
+pub(crate) fn parse_steps(s: &str) -> Result<Vec<Duration>, ParseDurationError> {
+    let steps = s
+        .split(',')
+        .map(|step| duration_parser::parse(step.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_steps() {
+        assert_eq!(
+            parse_steps("30m, 45m,20m"),
+            Ok(vec![
+                Duration::from_secs(30 * 60),
+                Duration::from_secs(45 * 60),
+                Duration::from_secs(20 * 60),
+            ])
+        );
+        assert_eq!(parse_steps("3m"), Ok(vec![Duration::from_secs(3 * 60)]));
+    }
+
+    #[test]
+    fn rejects_bad_steps() {
+        assert_eq!(parse_steps(""), Err(ParseDurationError::Empty));
+        assert_eq!(parse_steps("3m,"), Err(ParseDurationError::Empty));
+        assert!(parse_steps("3m, soon").is_err());
+    }
+}
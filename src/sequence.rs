@@ -0,0 +1,113 @@
+//! Pomodoro-style sequences, which alternate between work and break phases.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{duration_secs, optional_duration_secs};
+
+/// How a sequence is laid out. Loaded from the settings file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SequenceConfig {
+    #[serde(rename = "work_secs", with = "duration_secs")]
+    pub(crate) work: Duration,
+
+    #[serde(rename = "short_break_secs", with = "duration_secs")]
+    pub(crate) short_break: Duration,
+
+    #[serde(rename = "long_break_secs", with = "duration_secs")]
+    pub(crate) long_break: Duration,
+
+    /// Take a long break instead of a short one after this many work phases.
+    pub(crate) long_break_every: u32,
+
+    /// If set, automatically move on to the next phase this long after the alarm starts ringing, rather than
+    /// waiting for it to be acknowledged.
+    #[serde(rename = "auto_advance_secs", with = "optional_duration_secs")]
+    pub(crate) auto_advance: Option<Duration>,
+}
+
+impl Default for SequenceConfig {
+    fn default() -> Self {
+        Self {
+            work: Duration::from_secs(25 * 60),
+            short_break: Duration::from_secs(5 * 60),
+            long_break: Duration::from_secs(15 * 60),
+            long_break_every: 4,
+            auto_advance: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+/// Where a timer currently is within its sequence.
+#[derive(Clone, Debug)]
+pub(crate) struct Sequence {
+    pub(crate) config: SequenceConfig,
+    pub(crate) phase: Phase,
+
+    /// The current cycle, starting at 1. A cycle is a work phase and the break after it.
+    pub(crate) cycle: u32,
+}
+
+impl Sequence {
+    pub(crate) fn new(config: SequenceConfig) -> Self {
+        Self {
+            config,
+            phase: Phase::Work,
+            cycle: 1,
+        }
+    }
+
+    /// Goes back to the first work phase.
+    pub(crate) fn reset(&mut self) {
+        self.phase = Phase::Work;
+        self.cycle = 1;
+    }
+
+    /// How long the current phase lasts.
+    pub(crate) fn duration(&self) -> Duration {
+        match self.phase {
+            Phase::Work => self.config.work,
+            Phase::ShortBreak => self.config.short_break,
+            Phase::LongBreak => self.config.long_break,
+        }
+    }
+
+    /// Moves on to the next phase.
+    pub(crate) fn advance(&mut self) {
+        self.phase = match self.phase {
+            Phase::Work => {
+                // Note that if `long_break_every` is zero, this is never true, so we only take short breaks.
+                if self.cycle.is_multiple_of(self.config.long_break_every) {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                }
+            }
+            Phase::ShortBreak | Phase::LongBreak => {
+                self.cycle += 1;
+                Phase::Work
+            }
+        };
+    }
+
+    /// A short description of the current phase, e.g. "Work #2".
+    pub(crate) fn describe(&self) -> String {
+        let phase = match self.phase {
+            Phase::Work => "Work",
+            Phase::ShortBreak => "Short break",
+            Phase::LongBreak => "Long break",
+        };
+
+        format!("{phase} #{}", self.cycle)
+    }
+}
@@ -0,0 +1,452 @@
+//! Persistent user settings, stored as a TOML file under the platform config directory.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::Tone, recipe::Recipe, sequence::SequenceConfig, synth::SynthConfig,
+    timer_core::RingConfig, toml_file,
+};
+
+/// The current version of the settings file format. Bump this if the format changes in an incompatible way.
+const SETTINGS_VERSION: u32 = 1;
+
+const SETTINGS_DIR_NAME: &str = "timerys";
+const SETTINGS_FILE_NAME: &str = "settings.toml";
+
+pub(crate) const DEFAULT_WINDOW_WIDTH: f32 = 400.0;
+pub(crate) const DEFAULT_WINDOW_HEIGHT: f32 = 600.0;
+
+/// The window's last known size and position.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct WindowGeometry {
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    pub(crate) x: Option<i32>,
+    pub(crate) y: Option<i32>,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_WINDOW_WIDTH,
+            height: DEFAULT_WINDOW_HEIGHT,
+            x: None,
+            y: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Settings {
+    pub(crate) version: u32,
+
+    /// The last-used duration, which is what the timer is set to on startup.
+    #[serde(rename = "default_duration_secs", with = "duration_secs")]
+    pub(crate) default_duration: Duration,
+
+    /// A custom alarm sound. If unset, the bundled tone is used.
+    pub(crate) alarm_path: Option<PathBuf>,
+
+    /// Which bundled tone to ring with.
+    pub(crate) tone: Tone,
+
+    /// If set, new timers ring with a generated alarm instead of the bundled tone.
+    pub(crate) synth: Option<SynthConfig>,
+
+    /// Alarm volume, where 1.0 is the source's original volume.
+    pub(crate) volume: f32,
+
+    /// If set, the alarm fades in over this long.
+    #[serde(rename = "fade_in_secs", with = "optional_duration_secs")]
+    pub(crate) fade_in: Option<Duration>,
+
+    pub(crate) window: WindowGeometry,
+
+    /// The layout of Pomodoro sequences.
+    pub(crate) sequence: SequenceConfig,
+
+    /// How long timers ring for.
+    pub(crate) ring: RingConfig,
+
+    /// The durations offered as one-click presets.
+    #[serde(rename = "preset_secs", with = "duration_secs_list")]
+    pub(crate) presets: Vec<Duration>,
+
+    /// Whether picking a preset starts the timer straight away.
+    pub(crate) start_on_preset: bool,
+
+    /// Saved timers.
+    pub(crate) recipes: Vec<Recipe>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            default_duration: Duration::from_secs(5 * 60), // Default to 5 minutes
+            alarm_path: None,
+            tone: Tone::default(),
+            synth: None,
+            volume: 1.0,
+            fade_in: None,
+            window: WindowGeometry::default(),
+            sequence: SequenceConfig::default(),
+            ring: RingConfig::default(),
+            presets: [60, 5 * 60, 10 * 60, 25 * 60, 60 * 60]
+                .into_iter()
+                .map(Duration::from_secs)
+                .collect(),
+            start_on_preset: false,
+            recipes: vec![],
+        }
+    }
+}
+
+impl Settings {
+    /// Returns the path of the settings file, if there is a config directory on this platform.
+    pub(crate) fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(SETTINGS_DIR_NAME).join(SETTINGS_FILE_NAME))
+    }
+
+    /// Loads the settings from the default path. A missing file (or a missing config directory) results in the
+    /// default settings, but a file that exists and can't be parsed is an error.
+    pub(crate) fn load() -> eyre::Result<Self> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn load_from(path: &Path) -> eyre::Result<Self> {
+        let mut settings: Settings =
+            toml_file::load_versioned(path, "settings file", SETTINGS_VERSION)?;
+
+        if let Some(synth) = &mut settings.synth {
+            synth.clamp_pitch();
+        }
+
+        // Anything over 1.0 would amplify the alarm past its original volume.
+        settings.volume = if settings.volume.is_nan() {
+            1.0
+        } else {
+            settings.volume.clamp(0.0, 1.0)
+        };
+
+        // Hand-edited files can have a repeat that's no longer than the ring itself, which would never be used.
+        settings.ring.drop_unused_repeat();
+
+        Ok(settings)
+    }
+
+    /// Saves the settings to the default path, creating the config directory if needed.
+    pub(crate) fn save(&self) -> eyre::Result<()> {
+        match Self::path() {
+            Some(path) => self.save_to(&path),
+            None => Ok(()),
+        }
+    }
+
+    fn save_to(&self, path: &Path) -> eyre::Result<()> {
+        let settings = Settings {
+            version: SETTINGS_VERSION,
+            ..self.clone()
+        };
+
+        toml_file::save(path, "settings file", &settings)
+    }
+}
+
+/// (De)serializes a [`Duration`] as a number of seconds. Whole seconds are written as an integer, and anything finer
+/// as a fraction. When deserializing, a duration string like `"25m"` is also accepted.
+pub(crate) mod duration_secs {
+    use std::time::Duration;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::duration_parser;
+
+    /// A duration to be written out in seconds.
+    pub(super) struct Secs(pub(super) Duration);
+
+    impl Serialize for Secs {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if self.0.subsec_nanos() == 0 {
+                serializer.serialize_u64(self.0.as_secs())
+            } else {
+                serializer.serialize_f64(self.0.as_secs_f64())
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum SecsOrString {
+        Secs(u64),
+        FractionalSecs(f64),
+        String(String),
+    }
+
+    impl SecsOrString {
+        pub(super) fn into_duration<E: Error>(self) -> Result<Duration, E> {
+            match self {
+                SecsOrString::Secs(secs) => Ok(Duration::from_secs(secs)),
+                SecsOrString::FractionalSecs(secs) => {
+                    Duration::try_from_secs_f64(secs).map_err(E::custom)
+                }
+                SecsOrString::String(s) => duration_parser::parse(&s).map_err(E::custom),
+            }
+        }
+    }
+
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Secs(*duration).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        SecsOrString::deserialize(deserializer)?.into_duration()
+    }
+}
+
+/// (De)serializes an optional [`Duration`] like [`duration_secs`]. Use this alongside `#[serde(default)]`, as TOML
+/// has no null value, so `None` is just left out.
+pub(crate) mod optional_duration_secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::duration_secs::{Secs, SecsOrString};
+
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&Secs(*duration)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<SecsOrString>::deserialize(deserializer)?
+            .map(SecsOrString::into_duration)
+            .transpose()
+    }
+}
+
+/// (De)serializes a list of [`Duration`]s like [`duration_secs`].
+pub(crate) mod duration_secs_list {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::duration_secs::{Secs, SecsOrString};
+
+    pub(crate) fn serialize<S: Serializer>(
+        durations: &[Duration],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(durations.iter().copied().map(Secs))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Duration>, D::Error> {
+        Vec::<SecsOrString>::deserialize(deserializer)?
+            .into_iter()
+            .map(SecsOrString::into_duration)
+            .collect()
+    }
+}
+
+/// (De)serializes a [`SystemTime`](std::time::SystemTime) as a whole number of milliseconds since the Unix epoch.
+pub(crate) mod system_time_millis {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        time: &SystemTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let millis = time
+            .duration_since(UNIX_EPOCH)
+            .map_err(S::Error::custom)?
+            .as_millis();
+        serializer.serialize_u64(millis.try_into().map_err(S::Error::custom)?)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SystemTime, D::Error> {
+        let millis = u64::deserialize(deserializer)?;
+        UNIX_EPOCH
+            .checked_add(Duration::from_millis(millis))
+            .ok_or_else(|| D::Error::custom("time out of range"))
+    }
+}
+
+/// (De)serializes a [`Duration`] as a whole number of milliseconds, for things that are too short to be measured in
+/// seconds.
+pub(crate) mod duration_millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis().try_into().unwrap_or(u64::MAX))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// A settings file path in a fresh temporary directory, which is removed once the returned guard is dropped.
+    fn temp_settings_path() -> (TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("timerys").join(SETTINGS_FILE_NAME);
+        (dir, path)
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let (_dir, path) = temp_settings_path();
+
+        assert_eq!(Settings::load_from(&path).unwrap(), Settings::default());
+
+        let settings = Settings {
+            default_duration: Duration::from_secs(25 * 60),
+            alarm_path: Some(PathBuf::from("/tmp/alarm.ogg")),
+            volume: 0.5,
+            fade_in: Some(Duration::from_secs(10)),
+            window: WindowGeometry {
+                x: Some(10),
+                y: Some(20),
+                ..WindowGeometry::default()
+            },
+            presets: vec![Duration::from_secs(90)],
+            start_on_preset: true,
+            ..Settings::default()
+        };
+
+        settings.save_to(&path).unwrap();
+        assert_eq!(Settings::load_from(&path).unwrap(), settings);
+    }
+
+    #[test]
+    fn sub_second_durations_round_trip() {
+        let (_dir, path) = temp_settings_path();
+
+        let settings = Settings {
+            default_duration: Duration::from_millis(90_700),
+            fade_in: Some(Duration::from_millis(300)),
+            presets: vec![
+                Duration::from_millis(500),
+                Duration::from_secs(60),
+                Duration::from_millis(1_100),
+            ],
+            ..Settings::default()
+        };
+
+        settings.save_to(&path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("default_duration_secs = 90.7"));
+        assert!(contents.contains("fade_in_secs = 0.3"));
+        assert_eq!(Settings::load_from(&path).unwrap(), settings);
+    }
+
+    #[test]
+    fn malformed_settings_are_an_error() {
+        let (_dir, path) = temp_settings_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "default_duration_secs = [").unwrap();
+
+        let err = Settings::load_from(&path).unwrap_err();
+        assert!(err.to_string().starts_with("malformed settings file"));
+    }
+
+    #[test]
+    fn synth_pitch_is_clamped() {
+        let (_dir, path) = temp_settings_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        for (pitch, expected) in [
+            (0.0, 20.0),
+            (-440.0, 20.0),
+            (96_000.0, 20_000.0),
+            (440.0, 440.0),
+        ] {
+            fs::write(&path, format!("[synth]\npitch = {pitch:?}")).unwrap();
+
+            let settings = Settings::load_from(&path).unwrap();
+            assert_eq!(settings.synth.unwrap().pitch, expected);
+        }
+    }
+
+    #[test]
+    fn volume_is_clamped() {
+        let (_dir, path) = temp_settings_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        for (volume, expected) in [(5.0, 1.0), (-1.0, 0.0), (0.5, 0.5)] {
+            fs::write(&path, format!("volume = {volume:?}")).unwrap();
+            assert_eq!(Settings::load_from(&path).unwrap().volume, expected);
+        }
+    }
+
+    #[test]
+    fn unused_ring_repeat_is_dropped() {
+        let (_dir, path) = temp_settings_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        for (repeat, expected) in [
+            (30, None),
+            (60, None),
+            (120, Some(Duration::from_secs(120))),
+        ] {
+            fs::write(
+                &path,
+                format!("[ring]\nduration_secs = 60\nrepeat_secs = {repeat}"),
+            )
+            .unwrap();
+            assert_eq!(Settings::load_from(&path).unwrap().ring.repeat, expected);
+        }
+    }
+
+    #[test]
+    fn newer_settings_versions_are_rejected() {
+        let (_dir, path) = temp_settings_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, format!("version = {}", SETTINGS_VERSION + 1)).unwrap();
+
+        let err = Settings::load_from(&path).unwrap_err();
+        assert!(err.to_string().contains("only up to version"));
+    }
+}
@@ -0,0 +1,124 @@
+//! Timers that were running when the app last closed (or crashed), stored as a TOML file under the platform state
+//! directory so they can be picked back up on the next launch.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{timer::SavedTimer, toml_file};
+
+/// The current version of the state file format. Bump this if the format changes in an incompatible way.
+const STATE_VERSION: u32 = 1;
+
+const STATE_DIR_NAME: &str = "timerys";
+const STATE_FILE_NAME: &str = "timers.toml";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SavedTimers {
+    pub(crate) version: u32,
+
+    /// Which of the timers was selected, if any of them were.
+    pub(crate) selected: Option<usize>,
+
+    pub(crate) timers: Vec<SavedTimer>,
+}
+
+impl Default for SavedTimers {
+    fn default() -> Self {
+        Self {
+            version: STATE_VERSION,
+            selected: None,
+            timers: vec![],
+        }
+    }
+}
+
+impl SavedTimers {
+    /// Returns the path of the state file. Not every platform has a state directory, so this falls back to the data
+    /// directory.
+    pub(crate) fn path() -> Option<PathBuf> {
+        dirs::state_dir()
+            .or_else(dirs::data_dir)
+            .map(|dir| dir.join(STATE_DIR_NAME).join(STATE_FILE_NAME))
+    }
+
+    /// Loads the saved timers. A missing file means there aren't any.
+    pub(crate) fn load() -> eyre::Result<Self> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn load_from(path: &Path) -> eyre::Result<Self> {
+        toml_file::load_versioned(path, "state file", STATE_VERSION)
+    }
+
+    pub(crate) fn save(&self) -> eyre::Result<()> {
+        match Self::path() {
+            Some(path) => self.save_to(&path),
+            None => Ok(()),
+        }
+    }
+
+    fn save_to(&self, path: &Path) -> eyre::Result<()> {
+        toml_file::save(path, "state file", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+    use crate::{
+        audio::Tone, sequence::Phase, synth::SynthConfig, timer_core::SavedState,
+        timer_core::TimerMode,
+    };
+
+    #[test]
+    fn state_file_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("timerys").join(STATE_FILE_NAME);
+
+        assert_eq!(
+            SavedTimers::load_from(&path).unwrap(),
+            SavedTimers::default()
+        );
+
+        let deadline = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let saved = SavedTimers {
+            selected: Some(0),
+            timers: vec![SavedTimer {
+                label: "Tea".to_string(),
+                recipe: None,
+                mode: TimerMode::Countdown,
+                to_wait: Duration::from_secs(300),
+                steps: vec![],
+                step: 0,
+                phase: Phase::Work,
+                cycle: 1,
+                snoozes: 1,
+                laps: vec![],
+                alarm_path: None,
+                tone: Tone::Bell,
+                synth: Some(SynthConfig {
+                    pitch: 660.0,
+                    beeps: 2,
+                    ..SynthConfig::default()
+                }),
+                volume: Some(0.5),
+                fade_in: Some(Duration::from_secs(5)),
+                state: SavedState::Running {
+                    deadline,
+                    total_wait: Duration::from_secs(300),
+                },
+            }],
+            ..SavedTimers::default()
+        };
+
+        saved.save_to(&path).unwrap();
+        assert_eq!(SavedTimers::load_from(&path).unwrap(), saved);
+    }
+}
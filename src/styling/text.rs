@@ -6,3 +6,5 @@ pub(crate) const DISABLED_TEXT_COLOR: Color = Color {
 };
 
 pub(crate) const DEFAULT_TEXT_COLOR: Color = Color::BLACK;
+
+pub(crate) const ERROR_TEXT_COLOR: Color = Color::from_rgb(0.8, 0.1, 0.1);
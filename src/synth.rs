@@ -0,0 +1,255 @@
+//! Alarm tones that are generated on the fly rather than decoded from a file.
+
+use std::{f32::consts::PI, fmt, time::Duration};
+
+use rodio::{
+    source::{self, SineWave, Zero},
+    Source,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::duration_millis;
+
+/// The sample rate of generated tones. This matches [`SineWave`].
+const SAMPLE_RATE: u32 = 48000;
+
+/// How long each beep takes to ramp up, so it doesn't click.
+const BEEP_FADE_IN: Duration = Duration::from_millis(5);
+
+/// The range of pitches, in Hz, that a generated tone can have. The top is kept well under half the sample rate, as
+/// anything above that can't be represented and would alias into a different pitch.
+const MIN_PITCH: f32 = 20.0;
+const MAX_PITCH: f32 = 20_000.0;
+
+/// The shape of a generated tone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Waveform {
+    #[default]
+    Sine,
+    Square,
+    Triangle,
+    Sawtooth,
+}
+
+impl Waveform {
+    pub(crate) const ALL: [Waveform; 4] = [
+        Waveform::Sine,
+        Waveform::Square,
+        Waveform::Triangle,
+        Waveform::Sawtooth,
+    ];
+}
+
+impl fmt::Display for Waveform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Waveform::Sine => "Sine",
+            Waveform::Square => "Square",
+            Waveform::Triangle => "Triangle",
+            Waveform::Sawtooth => "Sawtooth",
+        })
+    }
+}
+
+/// A generated alarm: a number of beeps, repeated every `period`. For example, the default is 3 short beeps every
+/// 2 seconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SynthConfig {
+    /// The pitch of each beep, in Hz.
+    pub(crate) pitch: f32,
+    pub(crate) waveform: Waveform,
+
+    /// How many beeps there are in each repetition.
+    pub(crate) beeps: u32,
+
+    #[serde(rename = "beep_length_ms", with = "duration_millis")]
+    pub(crate) beep_length: Duration,
+
+    /// The silence between beeps.
+    #[serde(rename = "gap_ms", with = "duration_millis")]
+    pub(crate) gap: Duration,
+
+    /// How often the beeps repeat. If this is shorter than the beeps themselves, they just repeat back to back.
+    #[serde(rename = "period_ms", with = "duration_millis")]
+    pub(crate) period: Duration,
+}
+
+impl Default for SynthConfig {
+    fn default() -> Self {
+        Self {
+            pitch: 880.0,
+            waveform: Waveform::default(),
+            beeps: 3,
+            beep_length: Duration::from_millis(150),
+            gap: Duration::from_millis(100),
+            period: Duration::from_secs(2),
+        }
+    }
+}
+
+impl SynthConfig {
+    /// Brings the pitch back into the range that can be played, e.g. if the settings file was edited by hand.
+    pub(crate) fn clamp_pitch(&mut self) {
+        self.pitch = if self.pitch.is_nan() {
+            SynthConfig::default().pitch
+        } else {
+            self.pitch.clamp(MIN_PITCH, MAX_PITCH)
+        };
+    }
+
+    /// One repetition of the pattern: the beeps, then silence until the end of the period.
+    pub(crate) fn pattern(&self) -> Box<dyn Source<Item = f32> + Send> {
+        let mut parts: Vec<Box<dyn Source<Item = f32> + Send>> = vec![];
+        let mut length = Duration::ZERO;
+
+        for beep in 0..self.beeps {
+            if beep > 0 {
+                parts.push(Box::new(silence(self.gap)));
+                length += self.gap;
+            }
+
+            parts.push(Box::new(
+                self.tone()
+                    .take_duration(self.beep_length)
+                    .fade_in(BEEP_FADE_IN),
+            ));
+            length += self.beep_length;
+        }
+
+        parts.push(Box::new(silence(self.period.saturating_sub(length))));
+
+        Box::new(source::from_iter(parts))
+    }
+
+    /// An endless tone at the configured pitch and waveform.
+    fn tone(&self) -> Box<dyn Source<Item = f32> + Send> {
+        match self.waveform {
+            Waveform::Sine => Box::new(SineWave::new(self.pitch)),
+            waveform => Box::new(Oscillator {
+                waveform,
+                pitch: self.pitch,
+                sample: 0,
+            }),
+        }
+    }
+}
+
+fn silence(duration: Duration) -> Zero<f32> {
+    let samples = duration.as_secs_f64() * f64::from(SAMPLE_RATE);
+    Zero::new_samples(1, SAMPLE_RATE, samples as usize)
+}
+
+/// Generates the waveforms that rodio doesn't have a source for.
+struct Oscillator {
+    waveform: Waveform,
+    pitch: f32,
+    sample: u64,
+}
+
+impl Iterator for Oscillator {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // Where we are within the current cycle, from 0 to 1.
+        let phase =
+            (self.sample as f64 * f64::from(self.pitch) / f64::from(SAMPLE_RATE)).fract() as f32;
+        self.sample = self.sample.wrapping_add(1);
+
+        Some(match self.waveform {
+            Waveform::Sine => (2.0 * PI * phase).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Sawtooth => 2.0 * phase - 1.0,
+        })
+    }
+}
+
+impl Source for Oscillator {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_lasts_one_period() {
+        let config = SynthConfig::default();
+        let samples = config.pattern().count();
+
+        assert_eq!(samples, (2 * SAMPLE_RATE) as usize);
+    }
+
+    #[test]
+    fn pitch_is_clamped_to_what_can_be_played() {
+        for (pitch, expected) in [
+            (0.0, MIN_PITCH),
+            (-1.0, MIN_PITCH),
+            (f32::INFINITY, MAX_PITCH),
+            (f32::NAN, SynthConfig::default().pitch),
+            (440.0, 440.0),
+        ] {
+            let mut config = SynthConfig {
+                pitch,
+                ..SynthConfig::default()
+            };
+            config.clamp_pitch();
+
+            assert_eq!(config.pitch, expected);
+        }
+    }
+
+    #[test]
+    fn pattern_longer_than_period_is_not_padded() {
+        let config = SynthConfig {
+            beeps: 2,
+            beep_length: Duration::from_millis(500),
+            gap: Duration::from_millis(500),
+            period: Duration::from_millis(100),
+            ..SynthConfig::default()
+        };
+        let samples = config.pattern().count();
+
+        assert_eq!(samples, (3 * SAMPLE_RATE / 2) as usize);
+    }
+
+    #[test]
+    fn waveforms_stay_in_range() {
+        for waveform in Waveform::ALL {
+            let config = SynthConfig {
+                waveform,
+                ..SynthConfig::default()
+            };
+
+            assert!(
+                config
+                    .tone()
+                    .take(SAMPLE_RATE as usize)
+                    .all(|sample| (-1.0..=1.0).contains(&sample)),
+                "{waveform}"
+            );
+        }
+    }
+}
@@ -0,0 +1,468 @@
+//! A single named timer in the window. The app can hold several of these, each running (and ringing)
+//! independently.
+
+use std::{path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::{Alarm, Tone},
+    duration_parser,
+    recipe::Recipe,
+    sequence::Phase,
+    settings::{duration_millis, duration_secs_list, optional_duration_secs, Settings},
+    synth::SynthConfig,
+    timer_core::{
+        Clock, Lap, SavedState, SystemClock, TimerCore, TimerEvent, TimerMode, WallClock,
+    },
+};
+
+pub(crate) type TimerId = u64;
+
+/// Messages that are targeted at a specific [`Timer`].
+#[derive(Clone, Debug)]
+pub(crate) enum TimerMessage {
+    EnableEditTimer,
+    // DisableEditTimer,
+    EditNewNum(u32),
+    EditBackspace,
+    EditLabel(String),
+    Tick,
+    EnableTimer,
+    TogglePause,
+    ResetTimer,
+    StopRinging,
+    Snooze,
+    PreviewAlarm,
+    SetMode(TimerMode),
+    Lap,
+    /// Add this many seconds to a running countdown, or take them off if negative.
+    AdjustTime(i64),
+    UsePreset {
+        duration: Duration,
+        start: bool,
+    },
+}
+
+/// A started timer, as saved to the state file. See [`crate::state`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SavedTimer {
+    pub(crate) label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) recipe: Option<String>,
+    pub(crate) mode: TimerMode,
+    #[serde(rename = "to_wait_millis", with = "duration_millis")]
+    pub(crate) to_wait: Duration,
+    #[serde(default, rename = "steps_secs", with = "duration_secs_list")]
+    pub(crate) steps: Vec<Duration>,
+    #[serde(default)]
+    pub(crate) step: usize,
+    pub(crate) phase: Phase,
+    pub(crate) cycle: u32,
+    #[serde(default)]
+    pub(crate) snoozes: u32,
+    #[serde(default)]
+    pub(crate) laps: Vec<Lap>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) alarm_path: Option<PathBuf>,
+    pub(crate) tone: Tone,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) synth: Option<SynthConfig>,
+
+    /// If missing, the volume from the settings is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) volume: Option<f32>,
+    #[serde(default, rename = "fade_in_secs", with = "optional_duration_secs")]
+    pub(crate) fade_in: Option<Duration>,
+    pub(crate) state: SavedState,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum EditingState {
+    Editing(String),
+    NotEditing,
+}
+
+pub(crate) struct Timer<C: Clock = SystemClock> {
+    pub(crate) id: TimerId,
+    pub(crate) label: String,
+    pub(crate) core: TimerCore<C>,
+    pub(crate) is_editing: EditingState,
+    pub(crate) alarm: Alarm,
+
+    /// Why the alarm last failed to play, if it did. Shown as a banner until the timer is reset.
+    pub(crate) audio_error: Option<String>,
+
+    /// Set if the alarm couldn't be played while ringing, in which case the display flashes instead.
+    pub(crate) visual_alarm: bool,
+
+    /// The name of the recipe this timer was loaded from, until it's changed by hand.
+    pub(crate) recipe: Option<String>,
+}
+
+impl Timer {
+    pub(crate) fn new(id: TimerId, settings: &Settings) -> Self {
+        Self::with_clock(id, settings, SystemClock)
+    }
+
+    /// Recreates a saved timer. If it hit zero while the app was closed, it starts ringing straight away.
+    pub(crate) fn restore(
+        id: TimerId,
+        saved: &SavedTimer,
+        settings: &Settings,
+        wall_clock: WallClock,
+    ) -> Self {
+        let mut timer = Timer::new(id, settings);
+        timer.label = saved.label.clone();
+        timer.recipe = saved.recipe.clone();
+        timer.alarm.path = saved.alarm_path.clone();
+        timer.alarm.tone = saved.tone;
+        timer.alarm.synth = saved.synth.clone();
+        if let Some(synth) = &mut timer.alarm.synth {
+            synth.clamp_pitch();
+        }
+        if let Some(volume) = saved.volume {
+            timer.alarm.volume = volume;
+        }
+        timer.alarm.fade_in = saved.fade_in;
+
+        let core = &mut timer.core;
+        core.mode = saved.mode;
+        core.to_wait = saved.to_wait;
+        core.steps = saved.steps.clone();
+        core.step = saved.step;
+        core.sequence.phase = saved.phase;
+        core.sequence.cycle = saved.cycle;
+        core.snoozes = saved.snoozes;
+        core.laps = saved.laps.clone();
+
+        let event = core.restore_state(&saved.state, wall_clock);
+        timer.handle_event(event);
+
+        timer
+    }
+}
+
+impl<C: Clock> Timer<C> {
+    pub(crate) fn with_clock(id: TimerId, settings: &Settings, clock: C) -> Self {
+        let mut core =
+            TimerCore::with_clock(clock, settings.default_duration, settings.sequence.clone());
+        core.ring = settings.ring.clone();
+
+        Timer {
+            id,
+            label: String::new(),
+            core,
+            is_editing: EditingState::NotEditing,
+            alarm: Alarm::new(settings),
+            audio_error: None,
+            visual_alarm: false,
+            recipe: None,
+        }
+    }
+
+    /// What to save of this timer, if it's been started.
+    pub(crate) fn save(&self, wall_clock: WallClock) -> Option<SavedTimer> {
+        let core = &self.core;
+
+        Some(SavedTimer {
+            label: self.label.clone(),
+            recipe: self.recipe.clone(),
+            mode: core.mode,
+            to_wait: core.to_wait,
+            steps: core.steps.clone(),
+            step: core.step,
+            phase: core.sequence.phase,
+            cycle: core.sequence.cycle,
+            snoozes: core.snoozes,
+            laps: core.laps.clone(),
+            alarm_path: self.alarm.path.clone(),
+            tone: self.alarm.tone,
+            synth: self.alarm.synth.clone(),
+            volume: Some(self.alarm.volume),
+            fade_in: self.alarm.fade_in,
+            state: core.saved_state(wall_clock)?,
+        })
+    }
+
+    /// Stops the timer and sets it up from a recipe.
+    pub(crate) fn load_recipe(&mut self, recipe: &Recipe, settings: &Settings) {
+        self.update(TimerMessage::ResetTimer);
+        self.is_editing = EditingState::NotEditing;
+
+        self.label = recipe.name.clone();
+        self.recipe = Some(recipe.name.clone());
+        self.core.set_steps(recipe.steps.clone());
+
+        // Recipes without their own sound use the default one.
+        self.alarm.path = settings.alarm_path.clone();
+        self.alarm.tone = settings.tone;
+        self.alarm.synth = settings.synth.clone();
+
+        if let Some(path) = &recipe.alarm_path {
+            self.alarm.path = Some(path.clone());
+        } else if let Some(synth) = &recipe.synth {
+            self.alarm.path = None;
+            self.alarm.synth = Some(synth.clone());
+        } else if let Some(tone) = recipe.tone {
+            self.alarm.path = None;
+            self.alarm.tone = tone;
+            self.alarm.synth = None;
+        }
+    }
+
+    /// Goes back to a plain countdown, for when the duration is changed by hand.
+    fn forget_recipe(&mut self) {
+        self.recipe = None;
+        self.core.steps.clear();
+    }
+
+    fn update_to_wait_from_str(&mut self, s: &str) {
+        // This only ever contains digits, so this should only fail if it somehow gets too long.
+        if let Ok(to_wait) = duration_parser::parse_digit_entry_duration(s) {
+            self.forget_recipe();
+            self.core.to_wait = to_wait;
+        }
+    }
+
+    /// Starts or stops the alarm in response to the timer's state changing.
+    fn handle_event(&mut self, event: Option<TimerEvent>) {
+        match event {
+            Some(TimerEvent::StartedRinging | TimerEvent::AlarmRestarted) => {
+                let result = self.alarm.play();
+                self.visual_alarm = result.is_err();
+                self.record_audio_result(result);
+            }
+            Some(TimerEvent::AlarmStopped) => {
+                self.alarm.stop();
+                self.visual_alarm = false;
+            }
+            None => {}
+        }
+    }
+
+    /// Audio failures are never fatal; we log them and let the UI show a banner instead.
+    fn record_audio_result(&mut self, result: eyre::Result<()>) {
+        match result {
+            Ok(()) => {
+                self.audio_error = None;
+            }
+            Err(err) => {
+                eprintln!("Failed to play the alarm: {err:?}");
+                self.audio_error = Some(err.to_string());
+            }
+        }
+    }
+
+    pub(crate) fn update(&mut self, message: TimerMessage) {
+        match message {
+            TimerMessage::ResetTimer => {
+                self.core.reset();
+                self.alarm.stop();
+                self.audio_error = None;
+                self.visual_alarm = false;
+            }
+            TimerMessage::EditLabel(label) => {
+                self.label = label;
+            }
+            TimerMessage::Tick => {
+                let event = self.core.tick();
+                self.handle_event(event);
+            }
+            TimerMessage::TogglePause => {
+                self.core.toggle_pause();
+            }
+            TimerMessage::AdjustTime(secs) => {
+                let event = self.core.adjust(secs);
+                self.handle_event(event);
+            }
+            TimerMessage::UsePreset { duration, start } => {
+                if self.core.is_stopped() && self.core.mode == TimerMode::Countdown {
+                    self.forget_recipe();
+                    self.core.to_wait = duration;
+                    self.is_editing = EditingState::NotEditing;
+
+                    if start {
+                        self.core.start();
+                    }
+                }
+            }
+            TimerMessage::Lap => {
+                self.core.lap();
+            }
+            TimerMessage::StopRinging => {
+                if self.core.is_ringing() {
+                    self.alarm.stop();
+                    self.visual_alarm = false;
+                    self.core.acknowledge();
+                }
+            }
+            TimerMessage::Snooze => {
+                if self.core.is_ringing() {
+                    self.alarm.stop();
+                    self.visual_alarm = false;
+                    self.core.snooze();
+                }
+            }
+            TimerMessage::PreviewAlarm => {
+                if self.core.is_stopped() {
+                    let result = self.alarm.preview();
+                    self.record_audio_result(result);
+                }
+            }
+            TimerMessage::EnableTimer => {
+                if self.core.is_stopped() {
+                    self.core.start();
+                    self.is_editing = EditingState::NotEditing;
+                }
+            }
+            TimerMessage::SetMode(mode) => {
+                if self.core.is_stopped() {
+                    self.core.set_mode(mode);
+                    self.is_editing = EditingState::NotEditing;
+                }
+            }
+            TimerMessage::EnableEditTimer => {
+                if self.core.is_stopped() && self.core.mode == TimerMode::Countdown {
+                    self.is_editing = EditingState::Editing(String::new());
+                }
+            }
+            // TimerMessage::DisableEditTimer => {
+            //     self.is_editing = EditingState::NotEditing;
+            // }
+            TimerMessage::EditNewNum(new_digit) => {
+                if !self.core.is_stopped() {
+                    return;
+                }
+
+                let current = match &mut self.is_editing {
+                    EditingState::Editing(old_state) => {
+                        // TODO: For now, limit to 6 digits, can support more in the future.
+                        if old_state.len() >= 6 {
+                            return;
+                        }
+
+                        old_state.push_str(&new_digit.to_string());
+                        old_state.clone()
+                    }
+                    EditingState::NotEditing => {
+                        // This shouldn't happen, but if it does, then just flip things on.
+                        let s = new_digit.to_string();
+                        self.is_editing = EditingState::Editing(s.clone());
+                        s
+                    }
+                };
+
+                self.update_to_wait_from_str(&current);
+            }
+            TimerMessage::EditBackspace => {
+                if !self.core.is_stopped() {
+                    return;
+                }
+
+                let current = match &mut self.is_editing {
+                    EditingState::Editing(old_state) => {
+                        old_state.pop();
+                        old_state.clone()
+                    }
+                    EditingState::NotEditing => {
+                        // This shouldn't happen, but if it does, then it would just be the empty string anyway.
+                        // Flip it on and return the empty string.
+                        self.is_editing = EditingState::Editing(String::new());
+                        String::new()
+                    }
+                };
+
+                self.update_to_wait_from_str(&current);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rodio::{OutputStream, OutputStreamHandle, StreamError};
+
+    use super::*;
+    use crate::timer_core::MockClock;
+
+    fn no_output_device() -> Result<(OutputStream, OutputStreamHandle), StreamError> {
+        Err(StreamError::NoDevice)
+    }
+
+    fn timer_without_audio(to_wait: Duration) -> (Timer<MockClock>, MockClock) {
+        let settings = Settings {
+            default_duration: to_wait,
+            ..Settings::default()
+        };
+
+        let clock = MockClock::new();
+        let mut timer = Timer::with_clock(0, &settings, clock.clone());
+        timer.alarm.output = no_output_device;
+        (timer, clock)
+    }
+
+    #[test]
+    fn ringing_without_output_device_falls_back_to_visual_alarm() {
+        let (mut timer, _) = timer_without_audio(Duration::ZERO);
+
+        timer.update(TimerMessage::EnableTimer);
+        timer.update(TimerMessage::Tick);
+
+        assert!(timer.core.is_ringing());
+        assert!(timer.visual_alarm);
+        assert!(timer.audio_error.is_some());
+
+        timer.update(TimerMessage::StopRinging);
+        assert!(!timer.visual_alarm);
+    }
+
+    #[test]
+    fn ring_duration_stops_visual_alarm() {
+        let (mut timer, clock) = timer_without_audio(Duration::ZERO);
+        timer.core.ring.duration = Duration::from_secs(60);
+
+        timer.update(TimerMessage::EnableTimer);
+        timer.update(TimerMessage::Tick);
+        assert!(timer.visual_alarm);
+
+        clock.advance(Duration::from_secs(60));
+        timer.update(TimerMessage::Tick);
+
+        assert!(timer.core.is_ringing());
+        assert!(!timer.visual_alarm);
+    }
+
+    #[test]
+    fn restoring_keeps_the_timers_own_sound() {
+        let settings = Settings::default();
+        let mut timer = Timer::new(0, &settings);
+        timer.alarm.synth = Some(SynthConfig {
+            pitch: 440.0,
+            ..SynthConfig::default()
+        });
+        timer.alarm.volume = 0.5;
+        timer.alarm.fade_in = Some(Duration::from_secs(5));
+        timer.update(TimerMessage::EnableTimer);
+
+        let wall_clock = WallClock::now();
+        let saved = timer.save(wall_clock).unwrap();
+        let restored = Timer::restore(1, &saved, &settings, wall_clock);
+
+        assert_eq!(restored.alarm.synth, timer.alarm.synth);
+        assert_eq!(restored.alarm.volume, 0.5);
+        assert_eq!(restored.alarm.fade_in, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn preview_without_output_device_reports_error() {
+        let (mut timer, _) = timer_without_audio(Duration::from_secs(60));
+
+        timer.update(TimerMessage::PreviewAlarm);
+        assert!(timer.audio_error.is_some());
+        assert!(!timer.visual_alarm);
+
+        timer.update(TimerMessage::ResetTimer);
+        assert!(timer.audio_error.is_none());
+    }
+}
@@ -0,0 +1,1418 @@
+//! The timer state machine, independent of any front-end. Both the window and the headless terminal mode are
+//! driven by this.
+//!
+//! Rather than calling [`Instant::now`] directly, the state machine gets the time from a [`Clock`], so tests can
+//! control how time passes.
+
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    sequence::{Sequence, SequenceConfig},
+    settings::{duration_millis, duration_secs, optional_duration_secs, system_time_millis},
+};
+
+/// How far [`WallClock`] can drift from the system time before it's re-synced.
+const MAX_WALL_CLOCK_DRIFT: Duration = Duration::from_secs(1);
+
+/// A source of the current time.
+pub(crate) trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// A [`Clock`] that uses the actual system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Ties an [`Instant`] to the system time at the same moment, so instants can be converted to wall-clock time (and
+/// back) consistently. Converting with a fresh pair each time would give a slightly different answer every time.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct WallClock {
+    instant: Instant,
+    system_time: SystemTime,
+}
+
+impl WallClock {
+    pub(crate) fn now() -> Self {
+        Self {
+            instant: Instant::now(),
+            system_time: SystemTime::now(),
+        }
+    }
+
+    fn to_system_time(self, instant: Instant) -> Option<SystemTime> {
+        if instant >= self.instant {
+            self.system_time.checked_add(instant - self.instant)
+        } else {
+            self.system_time.checked_sub(self.instant - instant)
+        }
+    }
+
+    /// Re-syncs with the system time if it's drifted, e.g. because the computer was suspended (which pauses
+    /// [`Instant`]s on some platforms) or the clock was changed.
+    pub(crate) fn resync(&mut self) {
+        let now = WallClock::now();
+        let drift = match self.to_system_time(now.instant) {
+            Some(predicted) => match predicted.duration_since(now.system_time) {
+                Ok(drift) => drift,
+                Err(err) => err.duration(),
+            },
+            None => Duration::MAX,
+        };
+
+        if drift > MAX_WALL_CLOCK_DRIFT {
+            *self = now;
+        }
+    }
+}
+
+/// Splits a duration into hours, minutes and seconds, for display.
+pub(crate) fn human_duration(duration: Duration) -> (u64, u64, u64) {
+    // Ugly way to make it so it doesn't immediately round down to the nearest second.
+    let total_secs_f64 = duration.as_secs_f64();
+    let total_secs = if total_secs_f64 - total_secs_f64.trunc() > 0.1 {
+        total_secs_f64.ceil() as u64
+    } else {
+        total_secs_f64.floor() as u64
+    };
+
+    let hours = total_secs / (60 * 60);
+    let minutes = (total_secs % (60 * 60)) / 60;
+    let seconds = total_secs % 60;
+
+    (hours, minutes, seconds)
+}
+
+/// Whether a timer counts down to zero and rings, counts up from zero like a stopwatch, or counts down through a
+/// sequence of work and break phases.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TimerMode {
+    Countdown,
+    Stopwatch,
+    Sequence,
+}
+
+/// A recorded lap, with the total elapsed time and the time since the previous lap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Lap {
+    #[serde(rename = "total_millis", with = "duration_millis")]
+    pub(crate) total: Duration,
+    #[serde(rename = "split_millis", with = "duration_millis")]
+    pub(crate) split: Duration,
+}
+
+#[derive(Debug)]
+pub(crate) enum IsPaused {
+    Paused { pause_start: Instant },
+    NotPaused,
+}
+
+#[derive(Debug)]
+pub(crate) enum TimerAppState {
+    Started {
+        start_instant: Instant,
+        elapsed: Duration,
+        time_left: Duration,
+        total_wait: Duration,
+        is_paused: IsPaused,
+    },
+    Stopped,
+    Ringing {
+        /// When the countdown hit zero.
+        since: Instant,
+
+        /// Whether the alarm should currently be audible. See [`RingConfig`].
+        is_sounding: bool,
+
+        /// When the alarm was dismissed, if it has been. Once dismissed, it won't sound again.
+        acknowledged_at: Option<Instant>,
+
+        /// Overtime from before `since`, if the countdown hit zero while the app wasn't running to notice.
+        earlier_overtime: Duration,
+    },
+}
+
+/// Where a started timer is up to, in wall-clock time so it still makes sense after the app restarts.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub(crate) enum SavedState {
+    /// Counting down, and due to hit zero at `deadline`. A stopwatch counts up from `total_wait` before then.
+    Running {
+        #[serde(rename = "deadline_millis", with = "system_time_millis")]
+        deadline: SystemTime,
+        #[serde(rename = "total_wait_millis", with = "duration_millis")]
+        total_wait: Duration,
+    },
+    Paused {
+        #[serde(rename = "elapsed_millis", with = "duration_millis")]
+        elapsed: Duration,
+        #[serde(rename = "total_wait_millis", with = "duration_millis")]
+        total_wait: Duration,
+    },
+    /// Ringing since the countdown hit zero at `since`, and not dismissed yet.
+    Ringing {
+        #[serde(rename = "since_millis", with = "system_time_millis")]
+        since: SystemTime,
+    },
+}
+
+/// How long the alarm sounds for once a timer hits zero. Loaded from the settings file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct RingConfig {
+    /// How long the alarm sounds for. Zero means it rings until dismissed.
+    #[serde(rename = "duration_secs", with = "duration_secs")]
+    pub(crate) duration: Duration,
+
+    /// If set, the alarm sounds again this often (measured from when it first started) until it's dismissed. Only
+    /// used if it's longer than [`Self::duration`], since otherwise the alarm would never get a chance to pause.
+    #[serde(rename = "repeat_secs", with = "optional_duration_secs")]
+    pub(crate) repeat: Option<Duration>,
+
+    /// How long snoozing puts the alarm off for.
+    #[serde(rename = "snooze_secs", with = "duration_secs")]
+    pub(crate) snooze: Duration,
+
+    /// Whether to keep counting overtime after the alarm is dismissed, until the timer is reset.
+    pub(crate) overtime_after_dismiss: bool,
+}
+
+impl Default for RingConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(60),
+            repeat: None,
+            snooze: Duration::from_secs(5 * 60),
+            overtime_after_dismiss: false,
+        }
+    }
+}
+
+impl RingConfig {
+    /// Whether the alarm should be sounding, given how long the timer has been ringing for.
+    pub(crate) fn is_sounding(&self, ringing_for: Duration) -> bool {
+        if self.duration.is_zero() {
+            return true;
+        }
+
+        let into_repeat = match self.repeat() {
+            Some(repeat) => {
+                // Nanoseconds in a u64 cover centuries, so this can't realistically truncate.
+                Duration::from_nanos((ringing_for.as_nanos() % repeat.as_nanos()) as u64)
+            }
+            _ => ringing_for,
+        };
+
+        into_repeat < self.duration
+    }
+
+    /// The repeat interval, if it's set and long enough to actually be used. See [`Self::repeat`].
+    pub(crate) fn repeat(&self) -> Option<Duration> {
+        self.repeat
+            .filter(|repeat| !repeat.is_zero() && *repeat > self.duration)
+    }
+
+    /// Drops a repeat interval that would never be used, so it isn't shown or saved as if it were.
+    pub(crate) fn drop_unused_repeat(&mut self) {
+        self.repeat = self.repeat();
+    }
+}
+
+/// Something that happened while updating a [`TimerCore`] that a front-end needs to react to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TimerEvent {
+    /// The countdown hit zero, so the alarm should start.
+    StartedRinging,
+
+    /// The alarm has sounded for long enough, so it should stop. The timer is still ringing until it's
+    /// acknowledged, though.
+    AlarmStopped,
+
+    /// The alarm is set to repeat, and it's time for it to sound again.
+    AlarmRestarted,
+}
+
+pub(crate) struct TimerCore<C: Clock = SystemClock> {
+    clock: C,
+
+    pub(crate) mode: TimerMode,
+    pub(crate) state: TimerAppState,
+
+    /// What the countdown is set to.
+    pub(crate) to_wait: Duration,
+
+    pub(crate) laps: Vec<Lap>,
+    pub(crate) sequence: Sequence,
+    pub(crate) ring: RingConfig,
+
+    /// How many times the alarm has been snoozed since the timer was started.
+    pub(crate) snoozes: u32,
+
+    /// If there's more than one, the countdown goes through each of these in turn, moving on whenever the alarm
+    /// is acknowledged. See [`TimerCore::set_steps`].
+    pub(crate) steps: Vec<Duration>,
+
+    /// Which of the steps the countdown is on.
+    pub(crate) step: usize,
+}
+
+impl TimerCore {
+    pub(crate) fn new(to_wait: Duration, sequence: SequenceConfig) -> Self {
+        Self::with_clock(SystemClock, to_wait, sequence)
+    }
+}
+
+impl<C: Clock> TimerCore<C> {
+    pub(crate) fn with_clock(clock: C, to_wait: Duration, sequence: SequenceConfig) -> Self {
+        TimerCore {
+            clock,
+            mode: TimerMode::Countdown,
+            state: TimerAppState::Stopped,
+            to_wait,
+            laps: vec![],
+            sequence: Sequence::new(sequence),
+            ring: RingConfig::default(),
+            snoozes: 0,
+            steps: vec![],
+            step: 0,
+        }
+    }
+
+    /// The duration to show for this timer - the time left if it's counting down, the time elapsed if it's a
+    /// stopwatch, or what it's set to if it isn't running.
+    pub(crate) fn displayed_duration(&self) -> Duration {
+        match (self.mode, &self.state) {
+            (
+                TimerMode::Countdown | TimerMode::Sequence,
+                TimerAppState::Started { time_left, .. },
+            ) => *time_left,
+            // Round down to the second, so this doesn't get rounded up when displayed.
+            (TimerMode::Stopwatch, TimerAppState::Started { elapsed, .. }) => {
+                Duration::from_secs(elapsed.as_secs())
+            }
+            (TimerMode::Countdown, TimerAppState::Stopped) => self.to_wait,
+            (TimerMode::Sequence, TimerAppState::Stopped) => self.sequence.duration(),
+            (TimerMode::Stopwatch, TimerAppState::Stopped) | (_, TimerAppState::Ringing { .. }) => {
+                Duration::ZERO
+            }
+        }
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        matches!(self.state, TimerAppState::Stopped)
+    }
+
+    /// Whether the timer has been started, paused or not.
+    pub(crate) fn is_started(&self) -> bool {
+        matches!(self.state, TimerAppState::Started { .. })
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        matches!(
+            self.state,
+            TimerAppState::Started {
+                is_paused: IsPaused::NotPaused,
+                ..
+            }
+        )
+    }
+
+    pub(crate) fn is_ringing(&self) -> bool {
+        matches!(self.state, TimerAppState::Ringing { .. })
+    }
+
+    /// Whether it's ringing and hasn't been acknowledged yet.
+    pub(crate) fn is_unacknowledged(&self) -> bool {
+        matches!(
+            self.state,
+            TimerAppState::Ringing {
+                acknowledged_at: None,
+                ..
+            }
+        )
+    }
+
+    /// Formats the recorded laps as CSV, with times in seconds.
+    pub(crate) fn laps_csv(&self) -> String {
+        let mut csv = String::from("lap,lap_time,total_time\n");
+        for (index, lap) in self.laps.iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{:.3},{:.3}\n",
+                index + 1,
+                lap.split.as_secs_f64(),
+                lap.total.as_secs_f64()
+            ));
+        }
+
+        csv
+    }
+
+    /// Switches modes. Only does anything while stopped.
+    pub(crate) fn set_mode(&mut self, mode: TimerMode) {
+        if self.is_stopped() {
+            self.mode = mode;
+        }
+    }
+
+    /// Starts counting from the beginning.
+    pub(crate) fn start(&mut self) {
+        let total_wait = match self.mode {
+            TimerMode::Sequence => self.sequence.duration(),
+            TimerMode::Countdown | TimerMode::Stopwatch => self.to_wait,
+        };
+
+        self.snoozes = 0;
+        self.count_down(total_wait);
+    }
+
+    fn count_down(&mut self, total_wait: Duration) {
+        self.state = TimerAppState::Started {
+            start_instant: self.clock.now(),
+            elapsed: Duration::ZERO,
+            time_left: total_wait,
+            total_wait,
+            is_paused: IsPaused::NotPaused,
+        };
+    }
+
+    /// Stops the timer and clears any progress.
+    pub(crate) fn reset(&mut self) {
+        self.state = TimerAppState::Stopped;
+        self.laps.clear();
+        self.sequence.reset();
+        self.snoozes = 0;
+        self.go_to_step(0);
+    }
+
+    /// Stops the timer and sets it up to count down through each of the steps in turn. With no steps, this just
+    /// goes back to a plain countdown of whatever it was set to.
+    pub(crate) fn set_steps(&mut self, steps: Vec<Duration>) {
+        self.steps = steps;
+        self.mode = TimerMode::Countdown;
+        self.reset();
+    }
+
+    fn go_to_step(&mut self, step: usize) {
+        if let Some(&duration) = self.steps.get(step) {
+            self.step = step;
+            self.to_wait = duration;
+        }
+    }
+
+    /// Describes which step the countdown is on, e.g. "Step 2 of 3", if it has more than one.
+    pub(crate) fn describe_step(&self) -> Option<String> {
+        (self.mode == TimerMode::Countdown && self.steps.len() > 1)
+            .then(|| format!("Step {} of {}", self.step + 1, self.steps.len()))
+    }
+
+    /// Stops a ringing timer and has it count down again for the snooze length. Sequences stay on the same phase.
+    pub(crate) fn snooze(&mut self) {
+        if self.is_ringing() {
+            self.snoozes += 1;
+            self.count_down(self.ring.snooze);
+        }
+    }
+
+    /// Updates the elapsed and remaining time. This should be called regularly while the timer is running.
+    pub(crate) fn tick(&mut self) -> Option<TimerEvent> {
+        let now = self.clock.now();
+
+        if let TimerAppState::Ringing {
+            since,
+            is_sounding,
+            acknowledged_at: None,
+            ..
+        } = &mut self.state
+        {
+            let should_sound = self.ring.is_sounding(now.saturating_duration_since(*since));
+            if should_sound == *is_sounding {
+                return None;
+            }
+
+            *is_sounding = should_sound;
+            return Some(if should_sound {
+                TimerEvent::AlarmRestarted
+            } else {
+                TimerEvent::AlarmStopped
+            });
+        }
+
+        let TimerAppState::Started {
+            start_instant,
+            elapsed,
+            time_left,
+            total_wait,
+            is_paused: IsPaused::NotPaused,
+        } = &mut self.state
+        else {
+            return None;
+        };
+
+        *elapsed = now.saturating_duration_since(*start_instant);
+
+        if self.mode == TimerMode::Stopwatch {
+            return None;
+        }
+
+        let new_duration = total_wait.saturating_sub(*elapsed);
+        *time_left = new_duration;
+
+        if new_duration.is_zero() {
+            // Count from when it actually hit zero, rather than from this tick.
+            let overshoot = elapsed.saturating_sub(*total_wait);
+            self.state = TimerAppState::Ringing {
+                since: now.checked_sub(overshoot).unwrap_or(now),
+                is_sounding: true,
+                acknowledged_at: None,
+                earlier_overtime: Duration::ZERO,
+            };
+            Some(TimerEvent::StartedRinging)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn toggle_pause(&mut self) {
+        if let TimerAppState::Started {
+            start_instant,
+            is_paused,
+            ..
+        } = &mut self.state
+        {
+            match is_paused {
+                IsPaused::Paused { pause_start } => {
+                    *start_instant += self.clock.now().saturating_duration_since(*pause_start);
+                    *is_paused = IsPaused::NotPaused;
+                }
+                IsPaused::NotPaused => {
+                    *is_paused = IsPaused::Paused {
+                        pause_start: self.clock.now(),
+                    }
+                }
+            }
+        }
+    }
+
+    /// How long a started timer has been running for, right now rather than as of the last tick. Since resuming
+    /// shifts the start instant forward by however long we were paused, this excludes any paused time.
+    fn elapsed_now(&self, start_instant: Instant, is_paused: &IsPaused) -> Duration {
+        match is_paused {
+            IsPaused::Paused { pause_start } => {
+                pause_start.saturating_duration_since(start_instant)
+            }
+            IsPaused::NotPaused => self.clock.now().saturating_duration_since(start_instant),
+        }
+    }
+
+    /// Adds time to a running countdown, or takes time off if `secs` is negative. Taking off more than is left
+    /// makes it ring straight away, even if it's paused.
+    pub(crate) fn adjust(&mut self, secs: i64) -> Option<TimerEvent> {
+        if self.mode == TimerMode::Stopwatch {
+            return None;
+        }
+
+        let now = self.clock.now();
+        let TimerAppState::Started {
+            start_instant,
+            is_paused,
+            ..
+        } = &self.state
+        else {
+            return None;
+        };
+        let current_elapsed = self.elapsed_now(*start_instant, is_paused);
+
+        let TimerAppState::Started {
+            elapsed,
+            time_left,
+            total_wait,
+            ..
+        } = &mut self.state
+        else {
+            return None;
+        };
+
+        let amount = Duration::from_secs(secs.unsigned_abs());
+        *total_wait = if secs >= 0 {
+            total_wait.saturating_add(amount)
+        } else {
+            total_wait.saturating_sub(amount)
+        };
+        *elapsed = current_elapsed;
+        *time_left = total_wait.saturating_sub(current_elapsed);
+
+        if time_left.is_zero() {
+            self.state = TimerAppState::Ringing {
+                since: now,
+                is_sounding: true,
+                acknowledged_at: None,
+                earlier_overtime: Duration::ZERO,
+            };
+            Some(TimerEvent::StartedRinging)
+        } else {
+            None
+        }
+    }
+
+    /// Records a lap, if the timer is running.
+    pub(crate) fn lap(&mut self) {
+        if let TimerAppState::Started {
+            start_instant,
+            is_paused,
+            ..
+        } = &self.state
+        {
+            let total = self.elapsed_now(*start_instant, is_paused);
+            let previous = self.laps.last().map(|lap| lap.total).unwrap_or_default();
+
+            self.laps.push(Lap {
+                total,
+                split: total.saturating_sub(previous),
+            });
+        }
+    }
+
+    /// Acknowledges a ringing timer. Sequences (and countdowns with more steps left) move on to their next phase;
+    /// anything else stays put until reset, but won't sound again.
+    pub(crate) fn acknowledge(&mut self) {
+        let now = self.clock.now();
+        let TimerAppState::Ringing {
+            is_sounding,
+            acknowledged_at,
+            ..
+        } = &mut self.state
+        else {
+            return;
+        };
+
+        if self.mode == TimerMode::Sequence {
+            self.sequence.advance();
+            self.laps.clear();
+            self.start();
+        } else if self.mode == TimerMode::Countdown && self.step + 1 < self.steps.len() {
+            self.go_to_step(self.step + 1);
+            self.laps.clear();
+            self.start();
+        } else {
+            *is_sounding = false;
+            acknowledged_at.get_or_insert(now);
+        }
+    }
+
+    /// How long it's been since the countdown hit zero, if it's ringing. Unless [`RingConfig::overtime_after_dismiss`]
+    /// is set, this stops counting once the alarm is dismissed.
+    pub(crate) fn overtime(&self) -> Option<Duration> {
+        let TimerAppState::Ringing {
+            since,
+            acknowledged_at,
+            earlier_overtime,
+            ..
+        } = &self.state
+        else {
+            return None;
+        };
+
+        let until = match acknowledged_at {
+            Some(acknowledged_at) if !self.ring.overtime_after_dismiss => *acknowledged_at,
+            _ => self.clock.now(),
+        };
+
+        Some(earlier_overtime.saturating_add(until.saturating_duration_since(*since)))
+    }
+
+    /// Where the timer is up to, for saving. Stopped timers, and ones whose alarm has already been dismissed, have
+    /// nothing worth saving.
+    pub(crate) fn saved_state(&self, wall_clock: WallClock) -> Option<SavedState> {
+        match &self.state {
+            TimerAppState::Stopped
+            | TimerAppState::Ringing {
+                acknowledged_at: Some(_),
+                ..
+            } => None,
+            TimerAppState::Started {
+                start_instant,
+                total_wait,
+                is_paused: IsPaused::NotPaused,
+                ..
+            } => Some(SavedState::Running {
+                deadline: wall_clock
+                    .to_system_time(*start_instant)?
+                    .checked_add(*total_wait)?,
+                total_wait: *total_wait,
+            }),
+            TimerAppState::Started {
+                start_instant,
+                total_wait,
+                is_paused: is_paused @ IsPaused::Paused { .. },
+                ..
+            } => Some(SavedState::Paused {
+                elapsed: self.elapsed_now(*start_instant, is_paused),
+                total_wait: *total_wait,
+            }),
+            TimerAppState::Ringing {
+                since,
+                earlier_overtime,
+                ..
+            } => Some(SavedState::Ringing {
+                since: wall_clock
+                    .to_system_time(*since)?
+                    .checked_sub(*earlier_overtime)?,
+            }),
+        }
+    }
+
+    /// Picks up where a saved timer left off. If it hit zero while the app wasn't running, it starts ringing straight
+    /// away, with the overtime counted from when it should have.
+    pub(crate) fn restore_state(
+        &mut self,
+        saved: &SavedState,
+        wall_clock: WallClock,
+    ) -> Option<TimerEvent> {
+        let now = self.clock.now();
+        let wall_now = wall_clock.to_system_time(now)?;
+
+        // How long ago something happened, which is negative if it's still to come.
+        let ago = |time: SystemTime| match wall_now.duration_since(time) {
+            Ok(ago) => (ago, false),
+            Err(err) => (err.duration(), true),
+        };
+
+        let (elapsed, total_wait, is_paused) = match *saved {
+            SavedState::Running {
+                deadline,
+                total_wait,
+            } => {
+                let elapsed = match ago(deadline) {
+                    (overtime, false) => total_wait.saturating_add(overtime),
+                    (time_left, true) => total_wait.saturating_sub(time_left),
+                };
+                (elapsed, total_wait, IsPaused::NotPaused)
+            }
+            SavedState::Paused {
+                elapsed,
+                total_wait,
+            } => (elapsed, total_wait, IsPaused::Paused { pause_start: now }),
+            SavedState::Ringing { since } => {
+                let (overtime, _) = ago(since);
+                (overtime, Duration::ZERO, IsPaused::NotPaused)
+            }
+        };
+
+        if self.mode != TimerMode::Stopwatch && elapsed >= total_wait {
+            // This is measured separately from `since`, as an `Instant` can't always go back that far.
+            self.state = TimerAppState::Ringing {
+                since: now,
+                is_sounding: true,
+                acknowledged_at: None,
+                earlier_overtime: elapsed - total_wait,
+            };
+            return Some(TimerEvent::StartedRinging);
+        }
+
+        self.state = TimerAppState::Started {
+            start_instant: now.checked_sub(elapsed).unwrap_or(now),
+            elapsed,
+            time_left: total_wait.saturating_sub(elapsed),
+            total_wait,
+            is_paused,
+        };
+        None
+    }
+}
+
+/// A [`Clock`] that only moves when told to. Clones share the same time.
+#[cfg(test)]
+#[derive(Clone)]
+pub(crate) struct MockClock(std::rc::Rc<std::cell::Cell<Instant>>);
+
+#[cfg(test)]
+impl MockClock {
+    pub(crate) fn new() -> Self {
+        MockClock(std::rc::Rc::new(std::cell::Cell::new(Instant::now())))
+    }
+
+    pub(crate) fn advance(&self, duration: Duration) {
+        self.0.set(self.0.get() + duration);
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.0.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::Phase;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn timer(to_wait: Duration) -> (TimerCore<MockClock>, MockClock) {
+        let clock = MockClock::new();
+        let core = TimerCore::with_clock(clock.clone(), to_wait, SequenceConfig::default());
+        (core, clock)
+    }
+
+    #[test]
+    fn starts_stopped() {
+        let (core, _) = timer(secs(60));
+
+        assert!(core.is_stopped());
+        assert_eq!(core.displayed_duration(), secs(60));
+    }
+
+    #[test]
+    fn counts_down() {
+        let (mut core, clock) = timer(secs(60));
+        core.start();
+        assert!(core.is_running());
+        assert_eq!(core.displayed_duration(), secs(60));
+
+        clock.advance(secs(15));
+        assert_eq!(core.tick(), None);
+        assert_eq!(core.displayed_duration(), secs(45));
+    }
+
+    #[test]
+    fn rings_at_zero() {
+        let (mut core, clock) = timer(secs(60));
+        core.start();
+
+        clock.advance(Duration::from_millis(59_900));
+        assert_eq!(core.tick(), None);
+        assert!(core.is_running());
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+        assert!(core.is_ringing());
+        assert_eq!(core.displayed_duration(), Duration::ZERO);
+
+        // Only reported once.
+        clock.advance(secs(1));
+        assert_eq!(core.tick(), None);
+    }
+
+    #[test]
+    fn rings_when_overdue() {
+        let (mut core, clock) = timer(secs(60));
+        core.start();
+
+        clock.advance(secs(120));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+    }
+
+    #[test]
+    fn pausing_stops_the_countdown() {
+        let (mut core, clock) = timer(secs(60));
+        core.start();
+
+        clock.advance(secs(10));
+        core.tick();
+        core.toggle_pause();
+        assert!(!core.is_running());
+
+        clock.advance(secs(100));
+        assert_eq!(core.tick(), None);
+        assert_eq!(core.displayed_duration(), secs(50));
+    }
+
+    #[test]
+    fn resuming_excludes_paused_time() {
+        let (mut core, clock) = timer(secs(60));
+        core.start();
+
+        clock.advance(secs(10));
+        core.toggle_pause();
+        clock.advance(secs(100));
+        core.toggle_pause();
+        assert!(core.is_running());
+
+        clock.advance(secs(5));
+        core.tick();
+        assert_eq!(core.displayed_duration(), secs(45));
+
+        clock.advance(secs(45));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+    }
+
+    #[test]
+    fn reset_stops_and_clears() {
+        let (mut core, clock) = timer(secs(60));
+        core.start();
+        clock.advance(secs(10));
+        core.lap();
+
+        core.reset();
+        assert!(core.is_stopped());
+        assert!(core.laps.is_empty());
+        assert_eq!(core.displayed_duration(), secs(60));
+
+        // Resetting a ringing timer works too.
+        core.start();
+        clock.advance(secs(60));
+        core.tick();
+        assert!(core.is_ringing());
+        core.reset();
+        assert!(core.is_stopped());
+    }
+
+    #[test]
+    fn acknowledging_a_countdown_keeps_it_ringing() {
+        let (mut core, clock) = timer(secs(60));
+        core.start();
+        clock.advance(secs(60));
+        core.tick();
+
+        core.acknowledge();
+        assert!(core.is_ringing());
+    }
+
+    #[test]
+    fn stopwatch_counts_up_and_never_rings() {
+        let (mut core, clock) = timer(secs(60));
+        core.set_mode(TimerMode::Stopwatch);
+        core.start();
+
+        clock.advance(Duration::from_millis(90_500));
+        assert_eq!(core.tick(), None);
+        assert!(core.is_running());
+        assert_eq!(core.displayed_duration(), secs(90));
+    }
+
+    #[test]
+    fn mode_only_changes_while_stopped() {
+        let (mut core, _) = timer(secs(60));
+        core.start();
+        core.set_mode(TimerMode::Stopwatch);
+
+        assert_eq!(core.mode, TimerMode::Countdown);
+    }
+
+    #[test]
+    fn laps_exclude_paused_time() {
+        let (mut core, clock) = timer(secs(600));
+        core.set_mode(TimerMode::Stopwatch);
+        core.start();
+
+        clock.advance(secs(10));
+        core.lap();
+
+        core.toggle_pause();
+        clock.advance(secs(100));
+        // Laps while paused count up to when we paused.
+        core.lap();
+        core.toggle_pause();
+
+        clock.advance(secs(5));
+        core.lap();
+
+        assert_eq!(
+            core.laps,
+            vec![
+                Lap {
+                    total: secs(10),
+                    split: secs(10),
+                },
+                Lap {
+                    total: secs(10),
+                    split: Duration::ZERO,
+                },
+                Lap {
+                    total: secs(15),
+                    split: secs(5),
+                },
+            ]
+        );
+        assert_eq!(
+            core.laps_csv(),
+            "lap,lap_time,total_time\n1,10.000,10.000\n2,0.000,10.000\n3,5.000,15.000\n"
+        );
+    }
+
+    #[test]
+    fn laps_need_a_running_timer() {
+        let (mut core, _) = timer(secs(60));
+        core.lap();
+
+        assert!(core.laps.is_empty());
+    }
+
+    #[test]
+    fn sequence_advances_when_acknowledged() {
+        let (mut core, clock) = timer(secs(60));
+        core.sequence.config.long_break_every = 2;
+        core.set_mode(TimerMode::Sequence);
+        assert_eq!(core.displayed_duration(), core.sequence.config.work);
+
+        let mut phases = vec![];
+        core.start();
+        for _ in 0..4 {
+            clock.advance(core.sequence.duration());
+            assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+            core.acknowledge();
+            assert!(core.is_running());
+            phases.push((core.sequence.phase, core.sequence.cycle));
+        }
+
+        assert_eq!(
+            phases,
+            vec![
+                (Phase::ShortBreak, 1),
+                (Phase::Work, 2),
+                (Phase::LongBreak, 2),
+                (Phase::Work, 3),
+            ]
+        );
+
+        core.reset();
+        assert_eq!((core.sequence.phase, core.sequence.cycle), (Phase::Work, 1));
+    }
+
+    #[test]
+    fn stops_sounding_after_ring_duration() {
+        let (mut core, clock) = timer(secs(60));
+        core.start();
+
+        clock.advance(secs(60));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+        clock.advance(secs(59));
+        assert_eq!(core.tick(), None);
+
+        clock.advance(secs(1));
+        assert_eq!(core.tick(), Some(TimerEvent::AlarmStopped));
+
+        // It's quiet, but still waiting to be acknowledged.
+        assert!(core.is_ringing());
+        clock.advance(secs(600));
+        assert_eq!(core.tick(), None);
+    }
+
+    #[test]
+    fn ring_duration_counts_from_zero() {
+        let (mut core, clock) = timer(secs(60));
+        core.ring.duration = secs(30);
+        core.start();
+
+        // The tick comes late, but the ring duration is still measured from when the countdown hit zero.
+        clock.advance(secs(70));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+        clock.advance(secs(20));
+        assert_eq!(core.tick(), Some(TimerEvent::AlarmStopped));
+    }
+
+    #[test]
+    fn rings_until_dismissed() {
+        let (mut core, clock) = timer(secs(60));
+        core.ring.duration = Duration::ZERO;
+        core.start();
+
+        clock.advance(secs(60));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+        clock.advance(secs(60 * 60));
+        assert_eq!(core.tick(), None);
+        assert!(matches!(
+            core.state,
+            TimerAppState::Ringing {
+                is_sounding: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn repeats_until_acknowledged() {
+        let (mut core, clock) = timer(secs(60));
+        core.ring = RingConfig {
+            duration: secs(10),
+            repeat: Some(secs(120)),
+            ..RingConfig::default()
+        };
+        core.start();
+
+        clock.advance(secs(60));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+        clock.advance(secs(10));
+        assert_eq!(core.tick(), Some(TimerEvent::AlarmStopped));
+
+        clock.advance(secs(110));
+        assert_eq!(core.tick(), Some(TimerEvent::AlarmRestarted));
+
+        clock.advance(secs(10));
+        assert_eq!(core.tick(), Some(TimerEvent::AlarmStopped));
+
+        clock.advance(secs(60));
+        core.acknowledge();
+        assert!(core.is_ringing());
+
+        clock.advance(secs(60));
+        assert_eq!(core.tick(), None);
+    }
+
+    #[test]
+    fn ignores_a_repeat_no_longer_than_the_ring() {
+        let (mut core, clock) = timer(secs(60));
+        core.ring = RingConfig {
+            duration: secs(60),
+            repeat: Some(secs(60)),
+            ..RingConfig::default()
+        };
+        core.start();
+
+        clock.advance(secs(60));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+        clock.advance(secs(60));
+        assert_eq!(core.tick(), Some(TimerEvent::AlarmStopped));
+
+        clock.advance(secs(60));
+        assert_eq!(core.tick(), None);
+        assert!(core.is_ringing());
+    }
+
+    #[test]
+    fn snooze_counts_down_again() {
+        let (mut core, clock) = timer(secs(60));
+        core.start();
+
+        clock.advance(secs(60));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+        core.snooze();
+        assert!(core.is_running());
+        assert_eq!(core.snoozes, 1);
+        assert_eq!(core.displayed_duration(), secs(5 * 60));
+
+        clock.advance(secs(5 * 60));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+        core.snooze();
+        assert_eq!(core.snoozes, 2);
+
+        core.reset();
+        assert_eq!(core.snoozes, 0);
+    }
+
+    #[test]
+    fn snooze_only_when_ringing() {
+        let (mut core, _) = timer(secs(60));
+        core.snooze();
+        assert!(core.is_stopped());
+
+        core.start();
+        core.snooze();
+        assert_eq!(core.snoozes, 0);
+        assert_eq!(core.displayed_duration(), secs(60));
+    }
+
+    #[test]
+    fn snooze_keeps_sequence_phase() {
+        let (mut core, clock) = timer(secs(60));
+        core.set_mode(TimerMode::Sequence);
+        core.start();
+
+        clock.advance(secs(25 * 60));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+        core.snooze();
+        assert_eq!(core.sequence.phase, Phase::Work);
+        assert_eq!(core.displayed_duration(), secs(5 * 60));
+    }
+
+    #[test]
+    fn counts_overtime() {
+        let (mut core, clock) = timer(secs(60));
+        assert_eq!(core.overtime(), None);
+
+        core.start();
+        clock.advance(secs(30));
+        core.tick();
+        assert_eq!(core.overtime(), None);
+
+        // Measured from when it hit zero, even if the tick that noticed came later.
+        clock.advance(secs(35));
+        core.tick();
+        assert_eq!(core.overtime(), Some(secs(5)));
+
+        clock.advance(secs(78));
+        assert_eq!(core.overtime(), Some(secs(83)));
+    }
+
+    #[test]
+    fn overtime_stops_when_dismissed() {
+        let (mut core, clock) = timer(secs(60));
+        core.start();
+        clock.advance(secs(60));
+        core.tick();
+
+        clock.advance(secs(10));
+        core.acknowledge();
+        clock.advance(secs(10));
+        assert_eq!(core.overtime(), Some(secs(10)));
+
+        // Acknowledging again doesn't move it.
+        core.acknowledge();
+        assert_eq!(core.overtime(), Some(secs(10)));
+
+        core.reset();
+        assert_eq!(core.overtime(), None);
+    }
+
+    #[test]
+    fn overtime_can_continue_after_dismissal() {
+        let (mut core, clock) = timer(secs(60));
+        core.ring.overtime_after_dismiss = true;
+        core.start();
+        clock.advance(secs(60));
+        core.tick();
+
+        clock.advance(secs(10));
+        core.acknowledge();
+        clock.advance(secs(10));
+        assert_eq!(core.overtime(), Some(secs(20)));
+    }
+
+    /// A wall clock for `clock`, reading `system_secs` since the epoch at its current time.
+    fn wall_clock(clock: &MockClock, system_secs: u64) -> WallClock {
+        WallClock {
+            instant: clock.now(),
+            system_time: SystemTime::UNIX_EPOCH + secs(system_secs),
+        }
+    }
+
+    /// Saves the timer, then restores it into a fresh one `gap` later, as if the app had been closed in between.
+    fn restart(
+        core: &TimerCore<MockClock>,
+        clock: &MockClock,
+        gap: Duration,
+    ) -> TimerCore<MockClock> {
+        const SAVED_AT: u64 = 1_700_000_000;
+
+        let saved = core.saved_state(wall_clock(clock, SAVED_AT)).unwrap();
+
+        let (mut restored, new_clock) = timer(core.to_wait);
+        restored.restore_state(&saved, wall_clock(&new_clock, SAVED_AT + gap.as_secs()));
+        restored
+    }
+
+    #[test]
+    fn restores_a_running_timer() {
+        let (mut core, clock) = timer(secs(5 * 60));
+        core.start();
+        clock.advance(secs(60));
+
+        let restored = restart(&core, &clock, secs(60));
+        assert!(restored.is_running());
+        assert_eq!(restored.displayed_duration(), secs(3 * 60));
+    }
+
+    #[test]
+    fn restores_a_paused_timer() {
+        let (mut core, clock) = timer(secs(5 * 60));
+        core.start();
+        clock.advance(secs(60));
+        core.toggle_pause();
+
+        // Time spent closed doesn't count against a paused timer.
+        let restored = restart(&core, &clock, secs(10 * 60));
+        assert!(restored.is_started() && !restored.is_running());
+        assert_eq!(restored.displayed_duration(), secs(4 * 60));
+    }
+
+    #[test]
+    fn rings_if_the_deadline_passed_while_closed() {
+        let (mut core, clock) = timer(secs(5 * 60));
+        core.start();
+        clock.advance(secs(60));
+
+        let (mut restored, new_clock) = timer(secs(5 * 60));
+        let saved = core.saved_state(wall_clock(&clock, 1_000)).unwrap();
+        assert_eq!(
+            restored.restore_state(&saved, wall_clock(&new_clock, 1_000 + 6 * 60)),
+            Some(TimerEvent::StartedRinging)
+        );
+        assert!(restored.is_unacknowledged());
+        assert_eq!(restored.overtime(), Some(secs(2 * 60)));
+
+        new_clock.advance(secs(10));
+        assert_eq!(restored.overtime(), Some(secs(2 * 60 + 10)));
+
+        // And it keeps counting if the app is closed again before it's dismissed.
+        let restored = restart(&restored, &new_clock, secs(60));
+        assert_eq!(restored.overtime(), Some(secs(3 * 60 + 10)));
+    }
+
+    #[test]
+    fn skips_saving_finished_timers() {
+        let (mut core, clock) = timer(secs(60));
+        assert_eq!(core.saved_state(wall_clock(&clock, 1_000)), None);
+
+        core.start();
+        clock.advance(secs(60));
+        core.tick();
+        core.acknowledge();
+        assert_eq!(core.saved_state(wall_clock(&clock, 1_000)), None);
+    }
+
+    #[test]
+    fn adjusts_remaining_time() {
+        let (mut core, clock) = timer(secs(5 * 60));
+        core.start();
+        clock.advance(secs(60));
+
+        assert_eq!(core.adjust(60), None);
+        assert_eq!(core.displayed_duration(), secs(5 * 60));
+
+        assert_eq!(core.adjust(5 * 60), None);
+        assert_eq!(core.adjust(-60), None);
+        assert_eq!(core.displayed_duration(), secs(9 * 60));
+
+        clock.advance(secs(60));
+        core.tick();
+        assert_eq!(core.displayed_duration(), secs(8 * 60));
+    }
+
+    #[test]
+    fn adjusting_below_zero_rings() {
+        let (mut core, clock) = timer(secs(90));
+        core.start();
+        clock.advance(secs(60));
+
+        assert_eq!(core.adjust(-60), Some(TimerEvent::StartedRinging));
+        assert!(core.is_ringing());
+        assert_eq!(core.overtime(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn adjusts_while_paused() {
+        let (mut core, clock) = timer(secs(5 * 60));
+        core.start();
+        clock.advance(secs(60));
+        core.toggle_pause();
+
+        // Time spent paused doesn't count against the adjusted countdown.
+        clock.advance(secs(10 * 60));
+        assert_eq!(core.adjust(-2 * 60), None);
+        assert_eq!(core.displayed_duration(), secs(2 * 60));
+
+        core.toggle_pause();
+        clock.advance(secs(60));
+        core.tick();
+        assert_eq!(core.displayed_duration(), secs(60));
+
+        clock.advance(secs(60));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+    }
+
+    #[test]
+    fn adjusting_while_paused_can_ring() {
+        let (mut core, clock) = timer(secs(5 * 60));
+        core.start();
+        clock.advance(secs(4 * 60));
+        core.toggle_pause();
+
+        clock.advance(secs(60 * 60));
+        assert_eq!(core.adjust(-60), Some(TimerEvent::StartedRinging));
+    }
+
+    #[test]
+    fn no_adjusting_stopwatch_or_stopped() {
+        let (mut core, _) = timer(secs(60));
+        assert_eq!(core.adjust(60), None);
+        assert!(core.is_stopped());
+
+        core.set_mode(TimerMode::Stopwatch);
+        core.start();
+        assert_eq!(core.adjust(-60), None);
+        assert!(core.is_running());
+    }
+
+    #[test]
+    fn goes_through_steps() {
+        let (mut core, clock) = timer(secs(60));
+        core.set_steps(vec![secs(30), secs(45), secs(20)]);
+        assert_eq!(core.displayed_duration(), secs(30));
+        assert_eq!(core.describe_step().as_deref(), Some("Step 1 of 3"));
+
+        core.start();
+        clock.advance(secs(30));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+        core.acknowledge();
+        assert!(core.is_running());
+        assert_eq!(core.describe_step().as_deref(), Some("Step 2 of 3"));
+        assert_eq!(core.displayed_duration(), secs(45));
+
+        clock.advance(secs(45));
+        core.tick();
+        core.acknowledge();
+        clock.advance(secs(20));
+        assert_eq!(core.tick(), Some(TimerEvent::StartedRinging));
+
+        // The last step stays put once acknowledged.
+        core.acknowledge();
+        assert!(core.is_ringing());
+
+        core.reset();
+        assert_eq!(core.describe_step().as_deref(), Some("Step 1 of 3"));
+        assert_eq!(core.displayed_duration(), secs(30));
+    }
+
+    #[test]
+    fn single_step_is_a_plain_countdown() {
+        let (mut core, _) = timer(secs(60));
+        core.set_steps(vec![secs(3 * 60)]);
+
+        assert_eq!(core.describe_step(), None);
+        assert_eq!(core.displayed_duration(), secs(3 * 60));
+    }
+
+    #[test]
+    fn human_duration_splits_units() {
+        assert_eq!(human_duration(Duration::ZERO), (0, 0, 0));
+        assert_eq!(human_duration(secs(59)), (0, 0, 59));
+        assert_eq!(human_duration(secs(60)), (0, 1, 0));
+        assert_eq!(human_duration(secs(60 * 60)), (1, 0, 0));
+        assert_eq!(human_duration(secs(25 * 60 * 60 + 61)), (25, 1, 1));
+    }
+
+    #[test]
+    fn human_duration_rounds_up_partial_seconds() {
+        // A countdown that has just started shouldn't immediately drop a second.
+        assert_eq!(human_duration(Duration::from_millis(299_900)), (0, 5, 0));
+        assert_eq!(human_duration(Duration::from_millis(4_500)), (0, 0, 5));
+        assert_eq!(human_duration(Duration::from_millis(4_200)), (0, 0, 5));
+
+        // Rounding up can carry into the minutes and hours.
+        assert_eq!(human_duration(Duration::from_millis(59_500)), (0, 1, 0));
+        assert_eq!(human_duration(Duration::from_millis(3_599_500)), (1, 0, 0));
+    }
+
+    #[test]
+    fn human_duration_rounds_down_just_past_a_second() {
+        // Within a tenth of a second past a whole second rounds down, so a timer doesn't flicker between values
+        // right at a tick.
+        assert_eq!(human_duration(Duration::from_millis(4_050)), (0, 0, 4));
+        assert_eq!(human_duration(Duration::from_millis(4_100)), (0, 0, 4));
+        assert_eq!(human_duration(Duration::from_millis(4_101)), (0, 0, 5));
+        assert_eq!(human_duration(Duration::from_millis(50)), (0, 0, 0));
+        assert_eq!(human_duration(Duration::from_millis(150)), (0, 0, 1));
+    }
+}
@@ -0,0 +1,60 @@
+//! Reading and writing the versioned TOML files the app keeps, i.e. the settings and the saved timers.
+
+use std::{fs, path::Path};
+
+use eyre::{bail, WrapErr};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Just the version of a file, which is checked before the rest of it is parsed.
+#[derive(Deserialize)]
+struct Version {
+    #[serde(default)]
+    version: u32,
+}
+
+/// Loads a TOML file, checking that its version is no newer than `max_version`. A missing file gives the default.
+/// `kind` describes the file in error messages, e.g. "settings file".
+pub(crate) fn load_versioned<T: DeserializeOwned + Default>(
+    path: &Path,
+    kind: &str,
+    max_version: u32,
+) -> eyre::Result<T> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(T::default()),
+        Err(err) => {
+            return Err(err).wrap_err_with(|| format!("failed to read {kind} {}", path.display()))
+        }
+    };
+
+    let malformed = || format!("malformed {kind} {}", path.display());
+
+    // A newer version might not parse at all, so this is checked first to give a more useful error.
+    let Version { version } = toml::from_str(&contents).wrap_err_with(malformed)?;
+    if version > max_version {
+        bail!(
+            "{kind} {} has version {version}, but only up to version {max_version} is supported",
+            path.display(),
+        );
+    }
+
+    toml::from_str(&contents).wrap_err_with(malformed)
+}
+
+/// Saves a TOML file, creating its directory if needed. The file is written to the side and then moved into place,
+/// so a crash mid-write doesn't leave a broken file behind.
+pub(crate) fn save(path: &Path, kind: &str, value: &impl Serialize) -> eyre::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .wrap_err_with(|| format!("failed to create directory {}", parent.display()))?;
+    }
+
+    let contents =
+        toml::to_string_pretty(value).wrap_err_with(|| format!("failed to serialize {kind}"))?;
+
+    let temp_path = path.with_extension("toml.tmp");
+    fs::write(&temp_path, contents)
+        .wrap_err_with(|| format!("failed to write {kind} {}", temp_path.display()))?;
+    fs::rename(&temp_path, path)
+        .wrap_err_with(|| format!("failed to replace {kind} {}", path.display()))
+}
@@ -0,0 +1,318 @@
+//! A system tray icon that shows how long the selected timer has left, with a menu to control it. This is behind the
+//! `tray` feature, as it pulls in GTK on Linux.
+
+use std::any::TypeId;
+
+use eyre::WrapErr;
+use iced::{
+    futures::{channel::mpsc, future, SinkExt, StreamExt},
+    Subscription,
+};
+use tray_icon::{
+    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    Icon, TrayIcon, TrayIconBuilder,
+};
+
+/// The size of the generated icon, in pixels.
+const ICON_SIZE: u32 = 32;
+
+/// What was picked from the tray menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TrayAction {
+    ToggleWindow,
+    Start,
+    TogglePause,
+    Reset,
+    StopRinging,
+    Quit,
+}
+
+impl TrayAction {
+    const ALL: [TrayAction; 6] = [
+        TrayAction::ToggleWindow,
+        TrayAction::Start,
+        TrayAction::TogglePause,
+        TrayAction::Reset,
+        TrayAction::StopRinging,
+        TrayAction::Quit,
+    ];
+
+    /// The ID of this action's menu item.
+    fn id(self) -> &'static str {
+        match self {
+            TrayAction::ToggleWindow => "toggle_window",
+            TrayAction::Start => "start",
+            TrayAction::TogglePause => "toggle_pause",
+            TrayAction::Reset => "reset",
+            TrayAction::StopRinging => "stop_ringing",
+            TrayAction::Quit => "quit",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.id() == id)
+    }
+}
+
+/// What the tray shows, which mirrors the selected timer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct TrayStatus {
+    /// The window title, shown as the tooltip and at the top of the menu.
+    pub(crate) title: String,
+
+    /// How long is left, shown next to the icon where the platform supports it.
+    pub(crate) clock: String,
+
+    pub(crate) can_start: bool,
+    pub(crate) can_pause: bool,
+    pub(crate) is_paused: bool,
+    pub(crate) can_reset: bool,
+    pub(crate) can_stop_ringing: bool,
+    pub(crate) is_window_hidden: bool,
+}
+
+/// The menu items that change with the timer.
+struct TrayMenu {
+    status: MenuItem,
+    toggle_window: MenuItem,
+    start: MenuItem,
+    toggle_pause: MenuItem,
+    reset: MenuItem,
+    stop_ringing: MenuItem,
+}
+
+impl TrayMenu {
+    fn item(action: TrayAction, label: &str) -> MenuItem {
+        MenuItem::with_id(action.id(), label, true, None)
+    }
+
+    /// Builds the icon and its menu. This has to happen on the thread that runs the platform's event loop.
+    fn build() -> eyre::Result<(TrayIcon, TrayMenu)> {
+        let items = TrayMenu {
+            status: MenuItem::new("Timerys", false, None),
+            toggle_window: Self::item(TrayAction::ToggleWindow, "Hide window"),
+            start: Self::item(TrayAction::Start, "Start"),
+            toggle_pause: Self::item(TrayAction::TogglePause, "Pause"),
+            reset: Self::item(TrayAction::Reset, "Reset"),
+            stop_ringing: Self::item(TrayAction::StopRinging, "Stop ringing"),
+        };
+        let quit = Self::item(TrayAction::Quit, "Quit");
+
+        let menu = Menu::with_items(&[
+            &items.status,
+            &PredefinedMenuItem::separator(),
+            &items.start,
+            &items.toggle_pause,
+            &items.reset,
+            &items.stop_ringing,
+            &PredefinedMenuItem::separator(),
+            &items.toggle_window,
+            &quit,
+        ])
+        .wrap_err("failed to build the tray menu")?;
+
+        let icon = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_icon(icon()?)
+            .with_tooltip("Timerys")
+            .build()
+            .wrap_err("failed to create the tray icon")?;
+
+        Ok((icon, items))
+    }
+
+    fn apply(&self, icon: &TrayIcon, status: &TrayStatus) {
+        if let Err(err) = icon.set_tooltip(Some(&status.title)) {
+            eprintln!("Failed to update the tray tooltip: {err:?}");
+        }
+        icon.set_title(Some(&status.clock));
+
+        self.status.set_text(&status.title);
+        self.toggle_window.set_text(if status.is_window_hidden {
+            "Show window"
+        } else {
+            "Hide window"
+        });
+        self.start.set_enabled(status.can_start);
+        self.toggle_pause
+            .set_text(if status.is_paused { "Resume" } else { "Pause" });
+        self.toggle_pause.set_enabled(status.can_pause);
+        self.reset.set_enabled(status.can_reset);
+        self.stop_ringing.set_enabled(status.can_stop_ringing);
+    }
+}
+
+/// A clock face, drawn here so there isn't an image to decode.
+fn icon() -> eyre::Result<Icon> {
+    let center = (ICON_SIZE as f32 - 1.0) / 2.0;
+    let radius = ICON_SIZE as f32 / 2.0 - 1.0;
+
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let (dx, dy) = (x as f32 - center, y as f32 - center);
+            let distance = dx.hypot(dy);
+
+            let is_rim = distance <= radius && distance >= radius - 3.0;
+            let is_minute_hand = dx.abs() < 1.5 && dy <= 0.0 && -dy < radius - 5.0;
+            let is_hour_hand = dy.abs() < 1.5 && dx >= 0.0 && dx < radius - 9.0;
+
+            if is_rim || is_minute_hand || is_hour_hand {
+                rgba.extend_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+            } else if distance <= radius {
+                rgba.extend_from_slice(&[0x30, 0x30, 0x30, 0xff]);
+            } else {
+                rgba.extend_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+    }
+
+    Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE).wrap_err("failed to create the tray icon image")
+}
+
+/// The tray icon. On Linux, this lives on its own thread running GTK, so updates are sent over to it.
+pub(crate) struct Tray {
+    last_status: TrayStatus,
+
+    #[cfg(target_os = "linux")]
+    updates: std::sync::mpsc::Sender<TrayStatus>,
+
+    #[cfg(not(target_os = "linux"))]
+    icon: TrayIcon,
+
+    #[cfg(not(target_os = "linux"))]
+    menu: TrayMenu,
+}
+
+impl Tray {
+    #[cfg(target_os = "linux")]
+    pub(crate) fn new() -> eyre::Result<Self> {
+        use std::{sync::mpsc::TryRecvError, thread, time::Duration};
+
+        use gtk::glib::{self, ControlFlow};
+
+        /// How often the GTK thread picks up the latest status.
+        const UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+        let (updates, receiver) = std::sync::mpsc::channel::<TrayStatus>();
+        let (ready_sender, ready) = std::sync::mpsc::channel();
+
+        thread::spawn(move || {
+            let built = gtk::init()
+                .wrap_err("failed to initialize GTK")
+                .and_then(|()| TrayMenu::build());
+            let (icon, menu) = match built {
+                Ok(built) => {
+                    let _ = ready_sender.send(Ok(()));
+                    built
+                }
+                Err(err) => {
+                    let _ = ready_sender.send(Err(err));
+                    return;
+                }
+            };
+
+            glib::timeout_add_local(UPDATE_INTERVAL, move || {
+                // Only the latest status matters.
+                let mut latest = None;
+                loop {
+                    match receiver.try_recv() {
+                        Ok(status) => latest = Some(status),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            gtk::main_quit();
+                            return ControlFlow::Break;
+                        }
+                    }
+                }
+
+                if let Some(status) = latest {
+                    menu.apply(&icon, &status);
+                }
+
+                ControlFlow::Continue
+            });
+
+            gtk::main();
+        });
+
+        ready
+            .recv()
+            .wrap_err("the tray thread exited before the tray was created")??;
+
+        Ok(Self {
+            last_status: TrayStatus::default(),
+            updates,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn new() -> eyre::Result<Self> {
+        let (icon, menu) = TrayMenu::build()?;
+
+        Ok(Self {
+            last_status: TrayStatus::default(),
+            icon,
+            menu,
+        })
+    }
+
+    /// What the tray is currently showing.
+    pub(crate) fn status(&self) -> &TrayStatus {
+        &self.last_status
+    }
+
+    /// Whether the window has been hidden from the tray.
+    pub(crate) fn is_window_hidden(&self) -> bool {
+        self.last_status.is_window_hidden
+    }
+
+    pub(crate) fn set_status(&mut self, status: TrayStatus) {
+        if status == self.last_status {
+            return;
+        }
+
+        #[cfg(target_os = "linux")]
+        let _ = self.updates.send(status.clone());
+
+        #[cfg(not(target_os = "linux"))]
+        self.menu.apply(&self.icon, &status);
+
+        self.last_status = status;
+    }
+}
+
+/// Listens for picks from the tray menu.
+pub(crate) fn actions() -> Subscription<TrayAction> {
+    struct Actions;
+
+    iced::subscription::channel(TypeId::of::<Actions>(), 16, |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            if let Some(action) = TrayAction::from_id(&event.id().0) {
+                let _ = sender.unbounded_send(action);
+            }
+        }));
+
+        while let Some(action) = receiver.next().await {
+            let _ = output.send(action).await;
+        }
+
+        future::pending().await
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_ids_round_trip() {
+        for action in TrayAction::ALL {
+            assert_eq!(TrayAction::from_id(action.id()), Some(action));
+        }
+
+        assert_eq!(TrayAction::from_id("something-else"), None);
+    }
+}